@@ -0,0 +1,414 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::db::{self, FeedDb};
+use crate::feed::{fetch_feed_pages, CacheValidators, FetchError, FetchOptions};
+use crate::output::FeedOutput;
+
+/// Bumped whenever a request or response changes shape incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// One line of input. The `op` field picks the variant.
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request {
+    Hello,
+    ListFeeds,
+    Refresh {
+        url: String,
+        #[serde(default)]
+        force: bool,
+        /// Replace the stored title even if it was updated recently.
+        #[serde(default)]
+        refresh_metadata: bool,
+    },
+    MarkRead {
+        item: ItemId,
+        /// `false` marks the entry unread again.
+        read: Option<bool>,
+    },
+}
+
+/// Names an entry by its feed and the `key` that `list_feeds` reports.
+#[derive(Deserialize)]
+struct ItemId {
+    feed_url: String,
+    key: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Response<'a> {
+    Hello {
+        protocol_version: u32,
+        version: &'static str,
+    },
+    ListFeeds {
+        feeds: Vec<FeedOutput<'a>>,
+    },
+    Refresh {
+        url: String,
+        item_count: usize,
+        warnings: Vec<String>,
+        /// The feed answered 304 and its stored entries were kept.
+        not_modified: bool,
+    },
+    MarkRead {
+        feed_url: String,
+        key: String,
+        read: bool,
+        unread_count: usize,
+    },
+}
+
+/// Sent instead of a response when a request fails; `op` echoes the request's
+/// op when it could be read.
+#[derive(Serialize)]
+struct ErrorResponse {
+    op: Option<String>,
+    error: ApiError,
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    /// Stable identifier for programs to match on.
+    code: &'static str,
+    /// Human-readable detail; the wording may change.
+    message: String,
+}
+
+impl ApiError {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Answers newline-delimited JSON requests from `input` with one JSON line
+/// each on `output` until `input` ends. Failed requests get an error line and
+/// do not stop the loop.
+pub fn run_api(
+    path: &Path,
+    options: FetchOptions,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<()> {
+    let read_only = db::check_db_path(path, false)? == db::DbAccess::ReadOnly;
+    let mut db = if path.exists() {
+        db::load_db(path)?
+    } else {
+        FeedDb::default()
+    };
+    let runtime = tokio::runtime::Runtime::new()?;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match parse_request(&line) {
+            Ok(request) => {
+                let op = request_op(&request);
+                match handle_request(request, &mut db, path, read_only, &runtime, &options) {
+                    Ok(response) => serde_json::to_string(&response)?,
+                    Err(error) => serde_json::to_string(&ErrorResponse {
+                        op: Some(op.to_string()),
+                        error,
+                    })?,
+                }
+            }
+            Err((op, error)) => serde_json::to_string(&ErrorResponse { op, error })?,
+        };
+        writeln!(output, "{reply}")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn parse_request(line: &str) -> Result<Request, (Option<String>, ApiError)> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|err| (None, ApiError::new("invalid_json", err.to_string())))?;
+    let Some(op) = value.get("op").and_then(Value::as_str).map(str::to_string) else {
+        return Err((None, ApiError::new("missing_op", "request has no \"op\" string")));
+    };
+    serde_json::from_value(value).map_err(|err| {
+        let message = err.to_string();
+        let code = if message.starts_with("unknown variant") {
+            "unknown_op"
+        } else {
+            "invalid_request"
+        };
+        (Some(op), ApiError::new(code, message))
+    })
+}
+
+fn request_op(request: &Request) -> &'static str {
+    match request {
+        Request::Hello => "hello",
+        Request::ListFeeds => "list_feeds",
+        Request::Refresh { .. } => "refresh",
+        Request::MarkRead { .. } => "mark_read",
+    }
+}
+
+/// Runs one request, saving the database after changes. Refreshes and read
+/// marks are refused when the database is read-only, since they could not be
+/// kept.
+fn handle_request<'a>(
+    request: Request,
+    db: &'a mut FeedDb,
+    path: &Path,
+    read_only: bool,
+    runtime: &tokio::runtime::Runtime,
+    options: &FetchOptions,
+) -> Result<Response<'a>, ApiError> {
+    match request {
+        Request::Hello => Ok(Response::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            version: env!("CARGO_PKG_VERSION"),
+        }),
+        Request::ListFeeds => Ok(Response::ListFeeds {
+            feeds: db.feeds.iter().map(FeedOutput::from).collect(),
+        }),
+        Request::Refresh {
+            url,
+            force,
+            refresh_metadata,
+        } => {
+            let Some(index) = db.feeds.iter().position(|feed| feed.url == url) else {
+                return Err(ApiError::new("unknown_feed", format!("{url} is not subscribed")));
+            };
+            if read_only {
+                return Err(read_only_error(path));
+            }
+            // A forced refresh always downloads the feed again.
+            let validators = if force {
+                CacheValidators::default()
+            } else {
+                db.feeds[index].cache_validators()
+            };
+            let fetched = runtime.block_on(fetch_feed_pages(&url, 1, &validators, options));
+            let (index, not_modified) = match fetched {
+                Ok((meta, items)) => {
+                    let interval = Duration::days(i64::from(db::DEFAULT_METADATA_INTERVAL_DAYS));
+                    let update_metadata =
+                        force || refresh_metadata || db.metadata_due(&url, interval, Utc::now());
+                    (db.upsert_feed(url.clone(), meta, items, force, update_metadata), false)
+                }
+                Err(FetchError::NotModified { validators, .. }) => {
+                    db.record_not_modified(&url, validators);
+                    (index, true)
+                }
+                Err(err) => {
+                    let code = fetch_error_code(&err);
+                    let http_status = err.http_status();
+                    let mut message = describe(err);
+                    db.record_fetch_error(&url, message.clone(), http_status);
+                    // The fetch failure is what the caller asked about; a failed save rides along.
+                    if let Err(save) = db::save_db(path, db) {
+                        message.push_str(&format!(" (also failed to save: {})", describe(save)));
+                    }
+                    return Err(ApiError::new(code, message));
+                }
+            };
+            db::save_db(path, db).map_err(|err| ApiError::new("save_failed", describe(err)))?;
+            let feed = &db.feeds[index];
+            Ok(Response::Refresh {
+                url,
+                item_count: feed.items.len(),
+                warnings: feed.warnings.clone(),
+                not_modified,
+            })
+        }
+        Request::MarkRead { item, read } => {
+            let Some(feed) = db.feeds.iter_mut().find(|feed| feed.url == item.feed_url) else {
+                let message = format!("{} is not subscribed", item.feed_url);
+                return Err(ApiError::new("unknown_feed", message));
+            };
+            let Some(entry) = feed.items.iter_mut().find(|entry| entry.key() == item.key) else {
+                let message = format!("{} has no entry {}", item.feed_url, item.key);
+                return Err(ApiError::new("unknown_item", message));
+            };
+            if read_only {
+                return Err(read_only_error(path));
+            }
+            let read = read.unwrap_or(true);
+            entry.read = read;
+            let unread_count = feed.unread_count();
+            db::save_db(path, db).map_err(|err| ApiError::new("save_failed", describe(err)))?;
+            Ok(Response::MarkRead {
+                feed_url: item.feed_url,
+                key: item.key,
+                read,
+                unread_count,
+            })
+        }
+    }
+}
+
+fn read_only_error(path: &Path) -> ApiError {
+    let message = format!("{} is read-only; changes cannot be saved", path.display());
+    ApiError::new("read_only", message)
+}
+
+/// The error with its sources, as the CLI prints it.
+fn describe(err: impl Into<anyhow::Error>) -> String {
+    format!("{:#}", err.into())
+}
+
+fn fetch_error_code(err: &FetchError) -> &'static str {
+    match err {
+        FetchError::Request { .. } => "request_failed",
+        FetchError::Timeout { .. } => "timeout",
+        FetchError::Http { .. } => "http_error",
+        FetchError::NotModified { .. } => "not_modified",
+        FetchError::RateLimited { .. } => "rate_limited",
+        FetchError::TooLarge { .. } => "too_large",
+        FetchError::NotAFeed { .. } => "not_a_feed",
+        FetchError::Parse { .. } => "parse_failed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::feed::FeedMeta;
+    use crate::test_util::{conditional_server, item, response, serve, temp_dir};
+
+    /// Runs `requests` through one api session and returns each reply line.
+    fn converse(path: &Path, requests: &[&str]) -> Vec<Value> {
+        let input = requests.join("\n");
+        let mut output = Vec::new();
+        run_api(path, FetchOptions::default(), input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        output.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    /// A saved database subscribed to `url`, with one stored entry.
+    fn subscribed(name: &str, url: &str) -> PathBuf {
+        let path = temp_dir(name).join("feeds.json");
+        let mut db = FeedDb::default();
+        let items = vec![item("Stored", "https://example.com/stored")];
+        db.upsert_feed(url.to_string(), FeedMeta::default(), items, false, true);
+        db::save_db(&path, &db).unwrap();
+        path
+    }
+
+    #[test]
+    fn hello_reports_the_protocol_version() {
+        let path = temp_dir("api-hello").join("feeds.json");
+        let replies = converse(&path, &[r#"{"op":"hello"}"#]);
+        assert_eq!(replies[0]["op"], "hello");
+        assert_eq!(replies[0]["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(replies[0]["version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn list_feeds_reports_entries_with_their_keys() {
+        let path = subscribed("api-list", "https://example.com/feed.xml");
+        let replies = converse(&path, &[r#"{"op":"list_feeds"}"#]);
+        let feed = &replies[0]["feeds"][0];
+        assert_eq!(feed["url"], "https://example.com/feed.xml");
+        assert_eq!(feed["items"][0]["key"], "https://example.com/stored");
+        assert_eq!(feed["items"][0]["read"], false);
+    }
+
+    #[test]
+    fn bad_requests_get_error_codes_and_the_loop_goes_on() {
+        let path = temp_dir("api-errors").join("feeds.json");
+        let replies = converse(
+            &path,
+            &[
+                "not json",
+                r#"{"url":"https://example.com/feed.xml"}"#,
+                r#"{"op":"subscribe"}"#,
+                r#"{"op":"refresh"}"#,
+                "",
+                r#"{"op":"hello"}"#,
+            ],
+        );
+        let codes: Vec<&Value> = replies.iter().map(|reply| &reply["error"]["code"]).collect();
+        assert_eq!(codes[..4], ["invalid_json", "missing_op", "unknown_op", "invalid_request"]);
+        assert_eq!(replies[0]["op"], Value::Null);
+        assert_eq!(replies[2]["op"], "subscribe");
+        // The blank line gets no reply; the hello after it is still answered.
+        assert_eq!(replies.len(), 5);
+        assert_eq!(replies[4]["op"], "hello");
+    }
+
+    #[test]
+    fn refresh_saves_entries_then_reports_not_modified() {
+        let url = format!("{}/feed.xml", conditional_server("if-none-match", "\"v1\""));
+        let path = subscribed("api-refresh", &url);
+        let refresh = format!(r#"{{"op":"refresh","url":"{url}"}}"#);
+        let replies = converse(&path, &[&refresh, &refresh]);
+
+        assert_eq!(replies[0]["op"], "refresh", "{}", replies[0]);
+        assert_eq!(replies[0]["not_modified"], false);
+        // The fetched entry joins the stored one, which the feed no longer lists.
+        assert_eq!(replies[0]["item_count"], 2);
+        assert_eq!(replies[1]["not_modified"], true, "{}", replies[1]);
+        assert_eq!(replies[1]["item_count"], 2);
+        let feed = &db::load_db(&path).unwrap().feeds[0];
+        assert_eq!(feed.items[0].title, "one");
+        assert_eq!(feed.etag.as_deref(), Some("\"v1\""));
+    }
+
+    #[test]
+    fn refresh_of_an_unknown_feed_is_an_error() {
+        let path = temp_dir("api-unknown-feed").join("feeds.json");
+        let replies = converse(&path, &[r#"{"op":"refresh","url":"https://example.com/x"}"#]);
+        assert_eq!(replies[0]["op"], "refresh");
+        assert_eq!(replies[0]["error"]["code"], "unknown_feed");
+    }
+
+    #[test]
+    fn failed_refresh_is_recorded_on_the_feed() {
+        let url = format!("{}/feed.xml", serve(|_| response("404 Not Found", &[], "gone")));
+        let path = subscribed("api-failed-refresh", &url);
+
+        let replies = converse(&path, &[&format!(r#"{{"op":"refresh","url":"{url}"}}"#)]);
+        assert_eq!(replies[0]["error"]["code"], "http_error", "{}", replies[0]);
+
+        let feed = &db::load_db(&path).unwrap().feeds[0];
+        assert_eq!(feed.last_http_status, Some(404));
+        assert!(feed.last_error.as_deref().is_some_and(|err| err.contains("404")));
+    }
+
+    #[test]
+    fn mark_read_sets_and_clears_the_read_flag() {
+        let url = "https://example.com/feed.xml";
+        let path = subscribed("api-mark-read", url);
+        let mark = |read: Value| {
+            let item = serde_json::json!({ "feed_url": url, "key": "https://example.com/stored" });
+            serde_json::json!({ "op": "mark_read", "item": item, "read": read }).to_string()
+        };
+        let replies = converse(&path, &[&mark(Value::Null)]);
+        assert_eq!(replies[0]["read"], true, "{}", replies[0]);
+        assert_eq!(replies[0]["unread_count"], 0);
+        assert!(db::load_db(&path).unwrap().feeds[0].items[0].read);
+
+        let replies = converse(&path, &[&mark(Value::Bool(false))]);
+        assert_eq!(replies[0]["unread_count"], 1, "{}", replies[0]);
+        assert!(!db::load_db(&path).unwrap().feeds[0].items[0].read);
+    }
+
+    #[test]
+    fn mark_read_of_an_unknown_entry_is_an_error() {
+        let url = "https://example.com/feed.xml";
+        let path = subscribed("api-mark-unknown", url);
+        let request =
+            format!(r#"{{"op":"mark_read","item":{{"feed_url":"{url}","key":"missing"}}}}"#);
+        let replies = converse(&path, &[&request]);
+        assert_eq!(replies[0]["op"], "mark_read");
+        assert_eq!(replies[0]["error"]["code"], "unknown_item");
+    }
+}
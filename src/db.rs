@@ -1,12 +1,319 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+use crate::feed::{normalize_feed_url, validate_items, CacheValidators, FeedMeta};
+use crate::format::parse_published;
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FeedDb {
     pub feeds: Vec<FeedRecord>,
+    /// Policy for feeds added without one of their own.
+    #[serde(default)]
+    pub backfill: Backfill,
+    /// Days a stored entry is kept after its feed stops listing it.
+    #[serde(default = "default_prune_missing_after_days")]
+    pub prune_missing_after_days: u32,
+}
+
+impl Default for FeedDb {
+    fn default() -> Self {
+        Self {
+            feeds: Vec::new(),
+            backfill: Backfill::default(),
+            prune_missing_after_days: DEFAULT_PRUNE_MISSING_AFTER_DAYS,
+        }
+    }
+}
+
+pub const DEFAULT_PRUNE_MISSING_AFTER_DAYS: u32 = 30;
+
+fn default_prune_missing_after_days() -> u32 {
+    DEFAULT_PRUNE_MISSING_AFTER_DAYS
+}
+
+/// Which of a new feed's existing entries are stored when it is first added.
+/// Later refreshes store everything regardless.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backfill {
+    /// Every entry the feed lists.
+    #[default]
+    All,
+    /// Only entries published after the feed was added.
+    None,
+    /// Only entries published at most this many days before the feed was added.
+    Days(u32),
+}
+
+impl Backfill {
+    /// Whether an entry published at `published` is left out of a feed added
+    /// at `added_at`. Undated entries are always kept.
+    pub fn skips(self, published: Option<&str>, added_at: DateTime<Utc>) -> bool {
+        let Some(published) = parse_published(published) else {
+            return false;
+        };
+        match self {
+            Backfill::All => false,
+            Backfill::None => published < added_at,
+            Backfill::Days(days) => published < added_at - Duration::days(i64::from(days)),
+        }
+    }
+
+    /// Drops the entries the policy leaves out, returning how many there were.
+    pub fn apply(self, items: &mut Vec<FeedItem>, added_at: DateTime<Utc>) -> usize {
+        let before = items.len();
+        items.retain(|item| !self.skips(item.published.as_deref(), added_at));
+        before - items.len()
+    }
+}
+
+impl fmt::Display for Backfill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backfill::All => f.write_str("all"),
+            Backfill::None => f.write_str("none"),
+            Backfill::Days(days) => write!(f, "days:{days}"),
+        }
+    }
+}
+
+impl FromStr for Backfill {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "all" => Ok(Backfill::All),
+            "none" => Ok(Backfill::None),
+            _ => value
+                .strip_prefix("days:")
+                .and_then(|days| days.parse().ok())
+                .map(Backfill::Days)
+                .ok_or_else(|| format!("invalid backfill {value:?}; use all, none or days:N")),
+        }
+    }
+}
+
+// Stored as the same strings the command line accepts.
+impl Serialize for Backfill {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Backfill {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Summary counts over a whole database.
+#[derive(Debug, Default)]
+pub struct DbStats {
+    pub total_feeds: usize,
+    pub total_items: usize,
+    pub unread_items: usize,
+    pub feeds_with_errors: usize,
+    pub oldest_item_date: Option<DateTime<FixedOffset>>,
+    pub newest_item_date: Option<DateTime<FixedOffset>>,
+}
+
+impl FeedDb {
+    /// Drops items published before `cutoff`, along with undated ones.
+    pub fn retain_items_since(&mut self, cutoff: DateTime<Utc>) {
+        for feed in &mut self.feeds {
+            feed.items.retain(|item| {
+                parse_published(item.published.as_deref()).is_some_and(|date| date >= cutoff)
+            });
+        }
+    }
+
+    pub fn stats(&self) -> DbStats {
+        let dates = || {
+            self.feeds
+                .iter()
+                .flat_map(|feed| &feed.items)
+                .filter_map(|item| parse_published(item.published.as_deref()))
+        };
+        DbStats {
+            total_feeds: self.feeds.len(),
+            total_items: self.feeds.iter().map(|feed| feed.items.len()).sum(),
+            unread_items: self.feeds.iter().map(FeedRecord::unread_count).sum(),
+            feeds_with_errors: self.feeds.iter().filter(|feed| feed.last_error.is_some()).count(),
+            oldest_item_date: dates().min(),
+            newest_item_date: dates().max(),
+        }
+    }
+
+    /// Remembers a failed fetch on the stored feed so the error survives
+    /// restarts. The HTTP status is only replaced when the fetch got a response.
+    pub fn record_fetch_error(&mut self, url: &str, error: String, http_status: Option<u16>) {
+        if let Some(feed) = self.feeds.iter_mut().find(|feed| feed.url == url) {
+            feed.last_error = Some(error);
+            if http_status.is_some() {
+                feed.last_http_status = http_status;
+            }
+        }
+    }
+
+    /// Notes a conditional fetch that found the feed unchanged: the stored
+    /// entries stay as they are, any earlier error is cleared and validators
+    /// the 304 response sent replace the stored ones.
+    pub fn record_not_modified(&mut self, url: &str, validators: CacheValidators) {
+        if let Some(feed) = self.feeds.iter_mut().find(|feed| feed.url == url) {
+            if validators.etag.is_some() {
+                feed.etag = validators.etag;
+            }
+            if validators.last_modified.is_some() {
+                feed.last_modified = validators.last_modified;
+            }
+            feed.last_error = None;
+            feed.last_http_status = Some(304);
+            feed.rate_limited_until = None;
+        }
+    }
+
+    /// Index of the feed whose URL matches `url` once scheme, case and other
+    /// trivial differences are ignored.
+    pub fn find_duplicate(&self, url: &str) -> Option<usize> {
+        let normalized = normalize_feed_url(url);
+        self.feeds
+            .iter()
+            .position(|feed| normalize_feed_url(&feed.url) == normalized)
+    }
+
+    /// Index of the feed `url` names, matched like [`FeedDb::find_duplicate`],
+    /// or an error listing its close matches, for options that pick a feed.
+    pub fn find_feed(&self, url: &str) -> Result<usize, DbError> {
+        self.find_duplicate(url).ok_or_else(|| DbError::UnknownFeed {
+            url: url.to_string(),
+            close_matches: self.close_matches(url).into_iter().map(str::to_string).collect(),
+        })
+    }
+
+    /// URLs of stored feeds that look like what `url` was meant to be: on the
+    /// same host, or one address containing the other.
+    pub fn close_matches(&self, url: &str) -> Vec<&str> {
+        let wanted = normalize_feed_url(url);
+        let host = |url: &str| url.split('/').next().unwrap_or_default().to_string();
+        self.feeds
+            .iter()
+            .filter(|feed| {
+                let stored = normalize_feed_url(&feed.url);
+                host(&stored) == host(&wanted)
+                    || stored.contains(&wanted)
+                    || wanted.contains(&stored)
+            })
+            .map(|feed| feed.url.as_str())
+            .collect()
+    }
+
+    /// Whether a fetch of `url` should replace the stored title: always for a
+    /// feed that is new or predates the timestamp, otherwise once `interval`
+    /// has passed since it was last taken from the feed.
+    pub fn metadata_due(&self, url: &str, interval: Duration, now: DateTime<Utc>) -> bool {
+        let Some(feed) = self.feeds.iter().find(|feed| feed.url == url) else {
+            return true;
+        };
+        parse_published(feed.metadata_fetched_at.as_deref())
+            .is_none_or(|fetched| now - fetched.with_timezone(&Utc) >= interval)
+    }
+
+    /// Subscribes to a feed that is not stored yet, keeping only the entries
+    /// `backfill` allows. Returns its index and how many entries were skipped.
+    pub fn add_feed(
+        &mut self,
+        url: String,
+        meta: FeedMeta,
+        mut items: Vec<FeedItem>,
+        backfill: Backfill,
+    ) -> (usize, usize) {
+        let added_at = Utc::now();
+        let skipped = backfill.apply(&mut items, added_at);
+        let index = self.upsert_feed(url, meta, items, false, true);
+        let feed = &mut self.feeds[index];
+        feed.added_at = Some(added_at.to_rfc3339());
+        feed.backfill = backfill;
+        (index, skipped)
+    }
+
+    /// Inserts a freshly fetched feed, or updates the stored copy of it, and
+    /// returns its index. Fetched items are merged into the stored ones, see
+    /// [`merge_items`], and stored entries the feed stopped listing are pruned
+    /// once `prune_missing_after_days` have passed; `force` drops them at
+    /// once, even when the feed lists nothing. A stored feed keeps its title
+    /// unless `update_metadata` is set, see [`FeedDb::metadata_due`]. A stored
+    /// feed without entries, such as one imported from OPML, keeps only those
+    /// its backfill policy allows.
+    pub fn upsert_feed(
+        &mut self,
+        url: String,
+        meta: FeedMeta,
+        mut items: Vec<FeedItem>,
+        force: bool,
+        update_metadata: bool,
+    ) -> usize {
+        let fetched_at = Utc::now();
+        let now = fetched_at.to_rfc3339();
+        let keep_missing = Duration::days(i64::from(self.prune_missing_after_days));
+        if let Some(index) = self.feeds.iter().position(|feed| feed.url == url) {
+            let existing = &mut self.feeds[index];
+            if existing.items.is_empty() {
+                // The policy only drops entries from before the subscription,
+                // so it is safe to apply until the feed has stored anything.
+                existing.apply_backfill(&mut items);
+            }
+            let warnings = validate_items(&items, fetched_at);
+            if update_metadata {
+                existing.title = meta.title;
+                existing.metadata_fetched_at = Some(now);
+            }
+            existing.last_content_type = meta.content_type;
+            existing.last_http_status = meta.http_status;
+            existing.etag = meta.etag;
+            existing.last_modified = meta.last_modified;
+            if items.is_empty() && !existing.items.is_empty() && !force {
+                // An empty but valid feed is usually a publisher mistake; keep what we have.
+                existing.warnings = vec![format!(
+                    "feed returned 0 entries; kept {} stored items",
+                    existing.items.len()
+                )];
+            } else {
+                let keep_missing = (!force).then_some(keep_missing);
+                merge_items(&mut existing.items, items, keep_missing, fetched_at);
+                existing.warnings = warnings;
+            }
+            existing.last_error = None;
+            existing.rate_limited_until = None;
+            return index;
+        }
+        let warnings = validate_items(&items, fetched_at);
+        self.feeds.push(FeedRecord {
+            title: meta.title,
+            url,
+            items,
+            warnings,
+            last_error: None,
+            last_content_type: meta.content_type,
+            rate_limited_until: None,
+            last_http_status: meta.http_status,
+            preserve_order: true,
+            priority: DEFAULT_PRIORITY,
+            clamp_future_dates: true,
+            metadata_fetched_at: Some(now.clone()),
+            added_at: Some(now),
+            backfill: self.backfill,
+            etag: meta.etag,
+            last_modified: meta.last_modified,
+        });
+        self.feeds.len() - 1
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -14,44 +321,856 @@ pub struct FeedRecord {
     pub title: Option<String>,
     pub url: String,
     pub items: Vec<FeedItem>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub last_content_type: Option<String>,
+    #[serde(default)]
+    pub rate_limited_until: Option<String>,
+    #[serde(default)]
+    pub last_http_status: Option<u16>,
+    /// Show items in the order the feed lists them; when false they are
+    /// sorted newest first, like the All view.
+    #[serde(default = "default_preserve_order")]
+    pub preserve_order: bool,
+    /// Higher priority feeds are fetched first in bulk refreshes.
+    #[serde(default = "default_priority")]
+    pub priority: u8,
+    /// Keep entries dated in the future from sorting above everything else.
+    #[serde(default = "default_clamp_future_dates")]
+    pub clamp_future_dates: bool,
+    /// When the title was last taken from the feed; it is refreshed on a
+    /// slower cadence than the items.
+    #[serde(default)]
+    pub metadata_fetched_at: Option<String>,
+    /// When the feed was subscribed; unknown for feeds stored before this
+    /// was recorded.
+    #[serde(default)]
+    pub added_at: Option<String>,
+    /// The backfill policy the feed was added with.
+    #[serde(default)]
+    pub backfill: Backfill,
+    /// `ETag` of the last successful fetch, for conditional refreshes.
+    #[serde(default)]
+    pub etag: Option<String>,
+    /// `Last-Modified` of the last successful fetch.
+    #[serde(default)]
+    pub last_modified: Option<String>,
+}
+
+pub const DEFAULT_PRIORITY: u8 = 128;
+
+/// Days between routine updates of a stored feed's title.
+pub const DEFAULT_METADATA_INTERVAL_DAYS: u32 = 7;
+
+fn default_preserve_order() -> bool {
+    true
+}
+
+fn default_priority() -> u8 {
+    DEFAULT_PRIORITY
+}
+
+fn default_clamp_future_dates() -> bool {
+    true
+}
+
+impl FeedRecord {
+    /// Sorts items newest first; undated items go last, in their feed order.
+    pub fn sort_items_by_date(&mut self) {
+        self.items.sort_by_key(|item| {
+            std::cmp::Reverse(parse_published(item.published.as_deref()))
+        });
+    }
+
+    /// Drops the entries this feed's backfill policy leaves out of its first
+    /// fetch, for feeds that were subscribed before anything was fetched.
+    pub fn apply_backfill(&self, items: &mut Vec<FeedItem>) -> usize {
+        match parse_published(self.added_at.as_deref()) {
+            Some(added_at) => self.backfill.apply(items, added_at.with_timezone(&Utc)),
+            None => 0,
+        }
+    }
+
+    /// What to send so refreshing an unchanged feed costs no download.
+    pub fn cache_validators(&self) -> CacheValidators {
+        CacheValidators {
+            etag: self.etag.clone(),
+            last_modified: self.last_modified.clone(),
+        }
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.items.iter().filter(|item| !item.read).count()
+    }
+
+    /// Publication date of the newest dated item.
+    pub fn last_published(&self) -> Option<DateTime<FixedOffset>> {
+        self.items
+            .iter()
+            .filter_map(|item| parse_published(item.published.as_deref()))
+            .max()
+    }
+}
+
+/// Adds `fetched` to `stored`. Entries are matched by id, falling back to
+/// their link. Fetched entries come first in feed order, replacing their
+/// stored copies but keeping their read state, and are followed by the stored
+/// entries the feed no longer lists, which are usually the oldest. Those are
+/// kept for `keep_missing` after they were first found missing, or dropped
+/// straight away without it.
+fn merge_items(
+    stored: &mut Vec<FeedItem>,
+    mut fetched: Vec<FeedItem>,
+    keep_missing: Option<Duration>,
+    now: DateTime<Utc>,
+) {
+    let mut ids = HashMap::new();
+    let mut links = HashMap::new();
+    let mut linkless = HashMap::new();
+    for (index, item) in stored.iter().enumerate() {
+        if let Some(id) = &item.id {
+            ids.insert(id.clone(), index);
+        }
+        match &item.link {
+            Some(link) => links.insert(link.clone(), index),
+            None => linkless.insert((item.title.clone(), item.published.clone()), index),
+        };
+    }
+    let mut matched = vec![false; stored.len()];
+    for item in &mut fetched {
+        let by_id = item.id.as_ref().and_then(|id| ids.get(id));
+        let by_link = match &item.link {
+            Some(link) => links.get(link),
+            None => linkless.get(&(item.title.clone(), item.published.clone())),
+        };
+        if let Some(&index) = by_id.or(by_link) {
+            matched[index] = true;
+            item.read = stored[index].read;
+        }
+    }
+    let dropped: Vec<FeedItem> = stored
+        .drain(..)
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .filter_map(|(mut item, _)| {
+            let keep_for = keep_missing?;
+            let since = item.missing_since.get_or_insert_with(|| now.to_rfc3339());
+            parse_published(Some(since))
+                .is_none_or(|since| now - since.with_timezone(&Utc) < keep_for)
+                .then_some(item)
+        })
+        .collect();
+    *stored = fetched;
+    stored.extend(dropped);
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct FeedItem {
+    /// The entry's GUID or Atom id, used to recognise it across refreshes;
+    /// items saved before it was recorded fall back to their link.
+    #[serde(default)]
+    pub id: Option<String>,
     pub title: String,
     pub link: Option<String>,
     pub published: Option<String>,
+    /// The feed's date text when it could not be parsed, in which case
+    /// `published` is empty, and why it was rejected.
+    #[serde(default)]
+    pub published_raw: Option<String>,
+    #[serde(default)]
+    pub published_error: Option<String>,
+    /// Set when the feed gave no usable title and `title` was made up locally.
+    #[serde(default)]
+    pub title_synthesized: bool,
+    #[serde(default)]
+    pub read: bool,
+    /// Name of the entry's first author.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The entry's summary, or its content when it has none, as plain text.
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// When a refresh first found the entry gone from its feed; it is pruned
+    /// once it has been missing for `prune_missing_after_days`.
+    #[serde(default)]
+    pub missing_since: Option<String>,
+}
+
+impl FeedItem {
+    /// The entry's id, else its link, else its title; the same order refreshes
+    /// use to recognise a stored entry. The TUI and `api` address entries by it.
+    pub fn key(&self) -> String {
+        self.id
+            .clone()
+            .or_else(|| self.link.clone())
+            .unwrap_or_else(|| self.title.clone())
+    }
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum DbError {
+    #[error("failed to read database file {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to write database file {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse {format} in {}", path.display())]
+    Parse {
+        format: &'static str,
+        path: PathBuf,
+        #[source]
+        source: BoxError,
+    },
+    #[error("failed to serialize {format} for {}", path.display())]
+    Serialize {
+        format: &'static str,
+        path: PathBuf,
+        #[source]
+        source: BoxError,
+    },
+    #[error("unsupported database extension {0:?}; use .json, .yml, or .yaml")]
+    UnsupportedFormat(Option<String>),
+    #[error("database path {} is a directory, not a file", path.display())]
+    IsDirectory { path: PathBuf },
+    #[error(
+        "directory {} for the database does not exist; pass --create-dirs to create it",
+        path.display()
+    )]
+    MissingDirectory { path: PathBuf },
+    #[error("failed to create directory {}", path.display())]
+    CreateDirectory {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("no subscribed feed matches {url}{}", describe_close_matches(.close_matches))]
+    UnknownFeed {
+        url: String,
+        close_matches: Vec<String>,
+    },
+}
+
+fn describe_close_matches(matches: &[String]) -> String {
+    if matches.is_empty() {
+        String::new()
+    } else {
+        format!("; close matches: {}", matches.join(", "))
+    }
+}
+
+/// Whether changes can be saved back to a database path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DbAccess {
+    ReadWrite,
+    ReadOnly,
+}
+
+/// Checks a database path before it is used, so a bad path fails with a
+/// targeted error up front instead of on the first save. A missing parent
+/// directory is created when `create_dirs` is set.
+pub fn check_db_path(path: &Path, create_dirs: bool) -> Result<DbAccess, DbError> {
+    if path.is_dir() {
+        return Err(DbError::IsDirectory {
+            path: path.to_path_buf(),
+        });
+    }
+    DbFormat::from_path(path)?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if path.exists() {
+        // Saves replace the file through a temporary file beside it, so the
+        // directory has to be writable as well as the file itself.
+        let writable = fs::OpenOptions::new().append(true).open(path).is_ok()
+            && dir_writable(&fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+        return Ok(if writable { DbAccess::ReadWrite } else { DbAccess::ReadOnly });
+    }
+    if !parent.is_dir() {
+        if !create_dirs {
+            return Err(DbError::MissingDirectory {
+                path: parent.to_path_buf(),
+            });
+        }
+        fs::create_dir_all(parent).map_err(|source| DbError::CreateDirectory {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    Ok(if dir_writable(path) { DbAccess::ReadWrite } else { DbAccess::ReadOnly })
+}
+
+/// Tries creating and removing the temporary file a save of `path` would use.
+fn dir_writable(path: &Path) -> bool {
+    let temp = temp_path(path);
+    let created = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp)
+        .is_ok();
+    created && fs::remove_file(&temp).is_ok()
+}
+
+#[derive(Clone, Copy)]
+enum DbFormat {
+    Json,
+    Yaml,
+}
+
+impl DbFormat {
+    fn from_path(path: &Path) -> Result<Self, DbError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(DbFormat::Json),
+            Some("yml") | Some("yaml") => Ok(DbFormat::Yaml),
+            other => Err(DbError::UnsupportedFormat(other.map(str::to_string))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            DbFormat::Json => "JSON",
+            DbFormat::Yaml => "YAML",
+        }
+    }
+}
+
+pub fn load_db(path: &Path) -> Result<FeedDb, DbError> {
+    if path.is_dir() {
+        return Err(DbError::IsDirectory {
+            path: path.to_path_buf(),
+        });
+    }
+    let format = DbFormat::from_path(path)?;
+    let content = fs::read_to_string(path).map_err(|source| DbError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parsed: Result<FeedDb, BoxError> = match format {
+        DbFormat::Json => serde_json::from_str(&content).map_err(Into::into),
+        DbFormat::Yaml => serde_yaml::from_str(&content).map_err(Into::into),
+    };
+    parsed.map_err(|source| DbError::Parse {
+        format: format.name(),
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn save_db(path: &Path, db: &FeedDb) -> Result<(), DbError> {
+    let format = DbFormat::from_path(path)?;
+    let serialized: Result<String, BoxError> = match format {
+        DbFormat::Json => serde_json::to_string_pretty(db).map_err(Into::into),
+        DbFormat::Yaml => serde_yaml::to_string(db).map_err(Into::into),
+    };
+    let serialized = serialized.map_err(|source| DbError::Serialize {
+        format: format.name(),
+        path: path.to_path_buf(),
+        source,
+    })?;
+    write_atomic(path, serialized.as_bytes()).map_err(|source| DbError::Write {
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
-pub fn load_db(path: &Path) -> Result<FeedDb> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("failed to read database file {}", path.display()))?;
-    match path.extension().and_then(|ext| ext.to_str()) {
-        Some("json") => serde_json::from_str(&content)
-            .with_context(|| format!("failed to parse JSON in {}", path.display())),
-        Some("yml") | Some("yaml") => serde_yaml::from_str(&content)
-            .with_context(|| format!("failed to parse YAML in {}", path.display())),
-        other => Err(anyhow::anyhow!(
-            "unsupported database extension {:?}; use .json, .yml, or .yaml",
-            other
-        )),
-    }
-}
-
-pub fn save_db(path: &Path, db: &FeedDb) -> Result<()> {
-    let serialized = match path.extension().and_then(|ext| ext.to_str()) {
-        Some("json") => serde_json::to_string_pretty(db)
-            .with_context(|| format!("failed to serialize JSON for {}", path.display()))?,
-        Some("yml") | Some("yaml") => serde_yaml::to_string(db)
-            .with_context(|| format!("failed to serialize YAML for {}", path.display()))?,
-        other => {
-            return Err(anyhow::anyhow!(
-                "unsupported database extension {:?}; use .json, .yml, or .yaml",
-                other
-            ))
+/// Replaces `path` with `contents` so that a crash leaves either the old file
+/// or the new one, never a mix: the data goes to a temporary file in the same
+/// directory, which is synced and then renamed over the target. A symlinked
+/// database is written through to the file it points at.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let temp = temp_path(&target);
+    let result = (|| {
+        // Truncating rather than `create_new` lets a save reuse a file left
+        // behind by an earlier process that crashed with the same pid.
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp)?;
+        if let Ok(metadata) = fs::metadata(&target) {
+            file.set_permissions(metadata.permissions())?;
         }
+        file.write_all(contents)?;
+        file.flush()?;
+        file.sync_all()?;
+        fs::rename(&temp, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// The temporary file beside `path` that a save writes before renaming.
+fn temp_path(path: &Path) -> PathBuf {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
     };
-    fs::write(path, serialized)
-        .with_context(|| format!("failed to write database file {}", path.display()))?;
-    Ok(())
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    dir.join(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::html_to_text;
+    use crate::test_util::{item, temp_dir};
+
+    fn sample_db() -> FeedDb {
+        let mut db = FeedDb::default();
+        db.upsert_feed(
+            "https://example.com/feed.xml".to_string(),
+            FeedMeta::default(),
+            vec![item("First", "https://example.com/1")],
+            false,
+            true,
+        );
+        db
+    }
+
+    const DAY: Duration = Duration::days(1);
+
+    fn entry(id: &str, title: &str, summary_html: &str) -> FeedItem {
+        FeedItem {
+            id: Some(id.to_string()),
+            summary: Some(html_to_text(summary_html)),
+            ..item(title, &format!("https://example.com/{id}"))
+        }
+    }
+
+    fn merge(stored: &mut Vec<FeedItem>, fetched: Vec<FeedItem>, now: DateTime<Utc>) {
+        merge_items(stored, fetched, Some(Duration::days(30)), now);
+    }
+
+    fn titles(items: &[FeedItem]) -> Vec<&str> {
+        items.iter().map(|item| item.title.as_str()).collect()
+    }
+
+    #[test]
+    fn key_prefers_id_then_link_then_title() {
+        let link = "https://example.com/post";
+        let with_id = FeedItem {
+            id: Some("guid".to_string()),
+            ..item("Title", link)
+        };
+        assert_eq!(with_id.key(), "guid");
+        assert_eq!(item("Title", link).key(), link);
+        assert_eq!(FeedItem { link: None, ..item("Title", "") }.key(), "Title");
+    }
+
+    #[test]
+    fn read_flag_survives_while_entry_is_missing() {
+        let now = Utc::now();
+        let mut stored = vec![
+            FeedItem { read: true, ..entry("1", "First", "<p>One &amp; only</p>") },
+            entry("2", "Second", "<p>Two</p>"),
+        ];
+        merge(&mut stored, vec![entry("2", "Second", "<p>Two</p>")], now);
+
+        assert_eq!(titles(&stored), ["Second", "First"]);
+        assert!(stored[1].read);
+        assert_eq!(stored[1].summary.as_deref(), Some("One & only"));
+        assert_eq!(stored[1].missing_since, Some(now.to_rfc3339()));
+    }
+
+    #[test]
+    fn missing_entry_is_kept_inside_the_window() {
+        let now = Utc::now();
+        let since = (now - DAY * 29).to_rfc3339();
+        let mut stored = vec![FeedItem {
+            missing_since: Some(since.clone()),
+            ..entry("1", "First", "<p>One</p>")
+        }];
+        merge(&mut stored, vec![entry("2", "Second", "<b>Two</b>")], now);
+
+        assert_eq!(titles(&stored), ["Second", "First"]);
+        assert_eq!(stored[1].missing_since, Some(since));
+    }
+
+    #[test]
+    fn missing_entry_is_pruned_after_the_window() {
+        let now = Utc::now();
+        let mut stored = vec![FeedItem {
+            missing_since: Some((now - DAY * 31).to_rfc3339()),
+            ..entry("1", "First", "<p>One</p>")
+        }];
+        merge(&mut stored, vec![entry("2", "Second", "<b>Two</b>")], now);
+
+        assert_eq!(titles(&stored), ["Second"]);
+    }
+
+    #[test]
+    fn missing_since_is_cleared_when_entry_reappears() {
+        let now = Utc::now();
+        let mut stored = vec![FeedItem {
+            read: true,
+            missing_since: Some((now - DAY * 10).to_rfc3339()),
+            ..entry("1", "First", "<p>One</p>")
+        }];
+        merge(&mut stored, vec![entry("1", "First", "<p>One</p>")], now);
+
+        assert_eq!(titles(&stored), ["First"]);
+        assert!(stored[0].read);
+        assert_eq!(stored[0].missing_since, None);
+    }
+
+    #[test]
+    fn retitled_entry_with_same_guid_keeps_read() {
+        let mut stored = vec![FeedItem { read: true, ..entry("1", "Frist", "<p>One</p>") }];
+        merge(&mut stored, vec![entry("1", "First", "<p>One, fixed</p>")], Utc::now());
+
+        assert_eq!(titles(&stored), ["First"]);
+        assert!(stored[0].read);
+    }
+
+    #[test]
+    fn id_match_wins_over_a_changed_link() {
+        let mut stored = vec![FeedItem { read: true, ..entry("1", "First", "<p>One</p>") }];
+        let moved = FeedItem {
+            link: Some("https://example.com/moved".to_string()),
+            ..entry("1", "First", "<p>One</p>")
+        };
+        merge(&mut stored, vec![moved], Utc::now());
+
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].read);
+        assert_eq!(stored[0].link.as_deref(), Some("https://example.com/moved"));
+    }
+
+    #[test]
+    fn link_matches_entries_stored_without_an_id() {
+        let mut stored = vec![FeedItem {
+            id: None,
+            read: true,
+            ..entry("1", "First", "<p>One</p>")
+        }];
+        merge(&mut stored, vec![entry("1", "First", "<p>One</p>")], Utc::now());
+
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].read);
+    }
+
+    #[test]
+    fn linkless_entries_sharing_a_title_match_by_date() {
+        let linkless = |published: &str| FeedItem {
+            id: None,
+            link: None,
+            published: Some(published.to_string()),
+            ..entry("", "Weekly notes", "<p>Notes</p>")
+        };
+        let mut stored = vec![
+            linkless("2024-01-08T00:00:00+00:00"),
+            FeedItem { read: true, ..linkless("2024-01-01T00:00:00+00:00") },
+        ];
+        let fetched = vec![
+            linkless("2024-01-15T00:00:00+00:00"),
+            linkless("2024-01-08T00:00:00+00:00"),
+            linkless("2024-01-01T00:00:00+00:00"),
+        ];
+        merge(&mut stored, fetched, Utc::now());
+
+        let read: Vec<bool> = stored.iter().map(|item| item.read).collect();
+        assert_eq!(read, [false, false, true]);
+    }
+
+    /// Two hundred daily entries up to `added_at`, two published after it
+    /// and one undated, newest first.
+    #[test]
+    fn find_feed_ignores_trivial_differences_and_lists_close_matches() {
+        let db = sample_db();
+        assert_eq!(db.find_feed("HTTP://Example.com/feed.xml/").unwrap(), 0);
+
+        let err = db.find_feed("https://example.com/other.xml").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no subscribed feed matches https://example.com/other.xml; \
+             close matches: https://example.com/feed.xml"
+        );
+        let err = db.find_feed("https://elsewhere.org/feed").unwrap_err();
+        assert_eq!(err.to_string(), "no subscribed feed matches https://elsewhere.org/feed");
+    }
+
+    #[test]
+    fn stats_count_unread_entries_across_feeds() {
+        let mut db = sample_db();
+        let items = vec![
+            FeedItem { read: true, ..item("Read", "https://example.com/read") },
+            item("Unread", "https://example.com/unread"),
+        ];
+        let url = "https://example.com/other.xml".to_string();
+        db.upsert_feed(url, FeedMeta::default(), items, false, true);
+        let stats = db.stats();
+        assert_eq!(stats.total_feeds, 2);
+        assert_eq!(stats.total_items, 3);
+        assert_eq!(stats.unread_items, 2);
+    }
+
+    #[test]
+    fn empty_fetch_keeps_stored_entries_unless_forced() {
+        let mut db = sample_db();
+        let url = db.feeds[0].url.clone();
+        db.upsert_feed(url.clone(), FeedMeta::default(), Vec::new(), false, false);
+        assert_eq!(titles(&db.feeds[0].items), ["First"]);
+        assert_eq!(db.feeds[0].warnings, ["feed returned 0 entries; kept 1 stored items"]);
+
+        db.upsert_feed(url, FeedMeta::default(), Vec::new(), true, false);
+        assert!(db.feeds[0].items.is_empty());
+        assert!(db.feeds[0].warnings.is_empty());
+    }
+
+    const FEED: &str = "https://example.com/feed.xml";
+
+    fn titled(title: &str) -> FeedMeta {
+        FeedMeta {
+            title: Some(title.to_string()),
+            ..FeedMeta::default()
+        }
+    }
+
+    /// A feed whose title was just taken from the feed, and when that was.
+    fn titled_feed() -> (FeedDb, DateTime<Utc>) {
+        let mut db = FeedDb::default();
+        db.upsert_feed(FEED.to_string(), titled("Original"), Vec::new(), false, true);
+        let fetched = parse_published(db.feeds[0].metadata_fetched_at.as_deref()).unwrap();
+        (db, fetched.with_timezone(&Utc))
+    }
+
+    #[test]
+    fn routine_refresh_keeps_the_stored_title() {
+        let (mut db, fetched) = titled_feed();
+        assert!(!db.metadata_due(FEED, DAY * 7, fetched + DAY));
+        db.upsert_feed(FEED.to_string(), titled("Renamed"), Vec::new(), false, false);
+        assert_eq!(db.feeds[0].title.as_deref(), Some("Original"));
+        assert!(!db.metadata_due(FEED, DAY * 7, fetched + DAY));
+    }
+
+    #[test]
+    fn update_metadata_replaces_the_stored_title() {
+        let (mut db, fetched) = titled_feed();
+        db.feeds[0].metadata_fetched_at = Some((fetched - DAY * 30).to_rfc3339());
+        // `force` on its own leaves the title; callers pass it as `update_metadata` too.
+        db.upsert_feed(FEED.to_string(), titled("Forced"), Vec::new(), true, false);
+        assert_eq!(db.feeds[0].title.as_deref(), Some("Original"));
+        db.upsert_feed(FEED.to_string(), titled("Renamed"), Vec::new(), true, true);
+        assert_eq!(db.feeds[0].title.as_deref(), Some("Renamed"));
+        let refetched = parse_published(db.feeds[0].metadata_fetched_at.as_deref()).unwrap();
+        assert!(refetched.with_timezone(&Utc) >= fetched);
+    }
+
+    #[test]
+    fn metadata_falls_due_once_the_interval_passes() {
+        let (mut db, fetched) = titled_feed();
+        let interval = DAY * 7;
+        assert!(!db.metadata_due(FEED, interval, fetched + interval - Duration::seconds(1)));
+        assert!(db.metadata_due(FEED, interval, fetched + interval));
+        // Unknown feeds and feeds stored before the timestamp are always due.
+        assert!(db.metadata_due("https://example.com/other.xml", interval, fetched));
+        db.feeds[0].metadata_fetched_at = None;
+        assert!(db.metadata_due(FEED, interval, fetched));
+    }
+
+    fn long_history(added_at: DateTime<Utc>) -> Vec<FeedItem> {
+        let dated = |index: usize, published: DateTime<Utc>| FeedItem {
+            published: Some(published.to_rfc3339()),
+            ..item(&format!("Entry {index}"), &format!("https://example.com/{index}"))
+        };
+        let mut items = vec![
+            dated(202, added_at + Duration::hours(2)),
+            dated(201, added_at + Duration::hours(1)),
+        ];
+        for days in 0..200 {
+            let published = added_at - Duration::hours(12) - DAY * days;
+            items.push(dated(200 - days as usize, published));
+        }
+        items.push(FeedItem { published: None, ..item("Undated", "https://example.com/undated") });
+        items
+    }
+
+    fn added_at() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn backfill_all_keeps_the_whole_history() {
+        let mut items = long_history(added_at());
+        assert_eq!(Backfill::All.apply(&mut items, added_at()), 0);
+        assert_eq!(items.len(), 203);
+    }
+
+    #[test]
+    fn backfill_none_keeps_only_later_and_undated_entries() {
+        let mut items = long_history(added_at());
+        assert_eq!(Backfill::None.apply(&mut items, added_at()), 200);
+        assert_eq!(titles(&items), ["Entry 202", "Entry 201", "Undated"]);
+    }
+
+    #[test]
+    fn backfill_days_keeps_that_many_days_before_the_add() {
+        let mut items = long_history(added_at());
+        assert_eq!(Backfill::Days(30).apply(&mut items, added_at()), 170);
+        assert_eq!(items.len(), 33);
+        assert_eq!(items[31].title, "Entry 171");
+
+        let mut items = long_history(added_at());
+        assert_eq!(Backfill::Days(0).apply(&mut items, added_at()), 200);
+        let mut items = long_history(added_at());
+        assert_eq!(Backfill::Days(365).apply(&mut items, added_at()), 0);
+    }
+
+    #[test]
+    fn backfill_applies_at_the_add_and_never_on_refresh() {
+        let now = Utc::now();
+        let mut db = FeedDb::default();
+        let url = "https://example.com/feed.xml".to_string();
+        let (index, skipped) =
+            db.add_feed(url.clone(), FeedMeta::default(), long_history(now), Backfill::Days(30));
+
+        assert_eq!(skipped, 170);
+        assert_eq!(db.feeds[index].backfill, Backfill::Days(30));
+        assert!(db.feeds[index].added_at.is_some());
+
+        db.upsert_feed(url, FeedMeta::default(), long_history(now), false, false);
+        assert_eq!(db.feeds[index].items.len(), 203);
+    }
+
+    #[test]
+    fn backfill_applies_to_the_first_entries_of_an_imported_feed() {
+        let mut db = FeedDb::default();
+        let url = "https://example.com/feed.xml".to_string();
+        db.add_feed(url.clone(), FeedMeta::default(), Vec::new(), Backfill::None);
+
+        db.upsert_feed(url, FeedMeta::default(), long_history(Utc::now()), false, false);
+        assert_eq!(titles(&db.feeds[0].items), ["Entry 202", "Entry 201", "Undated"]);
+    }
+
+    #[test]
+    fn backfill_round_trips_through_its_string_form() {
+        for backfill in [Backfill::All, Backfill::None, Backfill::Days(30)] {
+            assert_eq!(backfill.to_string().parse::<Backfill>(), Ok(backfill));
+        }
+        assert_eq!("days:7".parse(), Ok(Backfill::Days(7)));
+        assert!("days:".parse::<Backfill>().is_err());
+        assert!("some".parse::<Backfill>().is_err());
+    }
+
+    #[test]
+    fn loading_a_missing_file_is_a_read_error() {
+        let path = temp_dir("missing").join("feeds.json");
+        match load_db(&path).unwrap_err() {
+            DbError::Read { source, .. } => {
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            }
+            err => panic!("expected a read error, got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn bad_json_is_a_parse_error() {
+        let path = temp_dir("bad-json").join("feeds.json");
+        fs::write(&path, "{\"feeds\": [").unwrap();
+        let err = load_db(&path).unwrap_err();
+        assert!(matches!(err, DbError::Parse { format: "JSON", .. }), "{err:?}");
+    }
+
+    #[test]
+    fn unknown_extension_is_unsupported() {
+        let err = load_db(Path::new("feeds.toml")).unwrap_err();
+        assert!(matches!(err, DbError::UnsupportedFormat(Some(ref ext)) if ext == "toml"));
+    }
+
+    #[test]
+    fn partial_write_leaves_original_untouched() {
+        let path = temp_dir("partial-write").join("feeds.json");
+        save_db(&path, &sample_db()).unwrap();
+        let original = fs::read(&path).unwrap();
+
+        // A save that dies mid-write leaves only a truncated temporary file.
+        let mut file = fs::File::create(temp_path(&path)).unwrap();
+        file.write_all(&original[..original.len() / 2]).unwrap();
+        drop(file);
+
+        assert_eq!(fs::read(&path).unwrap(), original);
+        assert_eq!(load_db(&path).unwrap().feeds.len(), 1);
+    }
+
+    #[test]
+    fn save_replaces_stale_temp_file() {
+        let path = temp_dir("stale-temp").join("feeds.json");
+        fs::write(temp_path(&path), "stale contents that are longer than nothing").unwrap();
+
+        save_db(&path, &sample_db()).unwrap();
+
+        assert!(!temp_path(&path).exists());
+        assert_eq!(load_db(&path).unwrap().feeds.len(), 1);
+    }
+
+    #[test]
+    fn check_db_path_rejects_a_directory() {
+        let dir = temp_dir("check-dir");
+        let err = check_db_path(&dir, true).unwrap_err();
+        assert!(matches!(err, DbError::IsDirectory { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn check_db_path_needs_create_dirs_for_a_missing_parent() {
+        let parent = temp_dir("check-parent").join("nested").join("dirs");
+        let path = parent.join("feeds.json");
+        match check_db_path(&path, false).unwrap_err() {
+            DbError::MissingDirectory { path: missing } => assert_eq!(missing, parent),
+            err => panic!("expected a missing directory, got {err:?}"),
+        }
+        assert!(!parent.exists());
+
+        assert_eq!(check_db_path(&path, true).unwrap(), DbAccess::ReadWrite);
+        assert!(parent.is_dir());
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_db_path_reports_read_only_files_and_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("check-read-only");
+        let path = dir.join("feeds.json");
+        save_db(&path, &FeedDb::default()).unwrap();
+        let new_path = dir.join("new.json");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o444)).unwrap();
+        // Permissions do not bind root, so there is nothing to check there.
+        let privileged = fs::File::create(dir.join("probe")).is_ok();
+        let accesses = [check_db_path(&path, false), check_db_path(&new_path, false)];
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        if privileged {
+            return;
+        }
+        for access in accesses {
+            assert_eq!(access.unwrap(), DbAccess::ReadOnly);
+        }
+    }
+
+    #[test]
+    fn check_db_path_leaves_no_probe_file() {
+        let dir = temp_dir("check-path");
+        let path = dir.join("feeds.yml");
+        assert_eq!(check_db_path(&path, false).unwrap(), DbAccess::ReadWrite);
+        save_db(&path, &FeedDb::default()).unwrap();
+        assert_eq!(check_db_path(&path, false).unwrap(), DbAccess::ReadWrite);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
 }
@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use quick_xml::escape::escape;
+use thiserror::Error;
+
+use crate::db::FeedDb;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+    /// OPML 2.0, which most feed readers can import.
+    Opml,
+    /// One feed URL per line.
+    UrlList,
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("failed to write export file {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Writes the subscription list of `db` to `path`, returning how many feeds
+/// it contains.
+pub fn write_export(db: &FeedDb, path: &Path, format: ExportFormat) -> Result<usize, ExportError> {
+    let content = match format {
+        ExportFormat::Opml => render_opml(db, Utc::now()),
+        ExportFormat::UrlList => render_url_list(db),
+    };
+    fs::write(path, content).map_err(|source| ExportError::Write {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(db.feeds.len())
+}
+
+/// An OPML 2.0 document with one flat `rss` outline per feed; feeds without a
+/// title are labelled with their URL.
+pub fn render_opml(db: &FeedDb, now: DateTime<Utc>) -> String {
+    let mut opml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    opml.push_str("<opml version=\"2.0\">\n");
+    opml.push_str("  <head>\n");
+    opml.push_str("    <title>rss-cli subscriptions</title>\n");
+    opml.push_str(&format!("    <dateCreated>{}</dateCreated>\n", now.to_rfc2822()));
+    opml.push_str("  </head>\n");
+    opml.push_str("  <body>\n");
+    for feed in &db.feeds {
+        let text = feed.title.as_deref().unwrap_or(&feed.url);
+        opml.push_str(&format!(
+            "    <outline type=\"rss\" text=\"{}\" xmlUrl=\"{}\"/>\n",
+            escape(text),
+            escape(feed.url.as_str())
+        ));
+    }
+    opml.push_str("  </body>\n");
+    opml.push_str("</opml>\n");
+    opml
+}
+
+pub fn render_url_list(db: &FeedDb) -> String {
+    db.feeds.iter().map(|feed| format!("{}\n", feed.url)).collect()
+}
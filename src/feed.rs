@@ -1,25 +1,1062 @@
-use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use feed_rs::parser::{ParseErrorKind, ParseFeedError};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::Semaphore;
 
 use crate::db::FeedItem;
+use crate::format::{describe_date_error, parse_date_fallback, parse_published};
+
+/// Responses larger than this are refused rather than parsed.
+const MAX_FEED_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Fetches `fetch_all` runs at once unless told otherwise.
+pub const DEFAULT_JOBS: u32 = 8;
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_RETRIES: u8 = 2;
+pub const DEFAULT_RETRY_BASE_MS: u64 = 500;
+
+/// How patient a fetch is with a slow or failing server.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct FetchOptions {
+    /// Limit on each request, from connecting to the end of the body.
+    pub timeout_secs: u64,
+    /// Further attempts after a timeout, connection failure or 5xx response.
+    pub retries: u8,
+    /// Wait before the first retry; each later retry waits twice as long.
+    pub retry_base_ms: u64,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            retries: DEFAULT_RETRIES,
+            retry_base_ms: DEFAULT_RETRY_BASE_MS,
+        }
+    }
+}
+
+impl FetchOptions {
+    /// The wait after failed attempt number `attempt`, counting from 0.
+    fn retry_delay(&self, attempt: u8) -> Duration {
+        let factor = 2u64.saturating_pow(u32::from(attempt));
+        Duration::from_millis(self.retry_base_ms.saturating_mul(factor))
+    }
+}
+
+/// Longest title taken from the first line of an entry's summary.
+const SYNTHESIZED_TITLE_CHARS: usize = 80;
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("failed to fetch feed {url}")]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("timed out fetching feed {url}")]
+    Timeout { url: String },
+    #[error("feed {url} returned HTTP {status}")]
+    Http { url: String, status: u16 },
+    #[error("feed {url} has not changed")]
+    NotModified {
+        url: String,
+        /// Validators the 304 response sent, which replace the stored ones.
+        validators: CacheValidators,
+    },
+    #[error("feed {url} is rate limited")]
+    RateLimited {
+        url: String,
+        /// Seconds until the limit resets, from `X-RateLimit-Reset`.
+        retry_after_secs: Option<u64>,
+    },
+    #[error("feed {url} is larger than {} MiB", MAX_FEED_BYTES / 1024 / 1024)]
+    TooLarge { url: String },
+    #[error("{url} is not an RSS, Atom or JSON feed")]
+    NotAFeed { url: String },
+    #[error("failed to parse feed {url}{location}")]
+    Parse {
+        url: String,
+        /// Where in the body parsing failed, e.g. " at line 3, near `<guid …`",
+        /// or empty when it is not known.
+        location: String,
+        #[source]
+        source: ParseFeedError,
+    },
+}
+
+impl FetchError {
+    /// The response code, for errors caused by one.
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            FetchError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    fn request(url: &str, source: reqwest::Error) -> Self {
+        if source.is_timeout() {
+            FetchError::Timeout {
+                url: url.to_string(),
+            }
+        } else {
+            FetchError::Request {
+                url: url.to_string(),
+                source,
+            }
+        }
+    }
+}
+
+/// Feed-level details returned alongside the parsed items.
+#[derive(Debug, Default)]
+pub struct FeedMeta {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub content_type: Option<String>,
+    pub http_status: Option<u16>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Values from an earlier response that make a fetch conditional, so an
+/// unchanged feed is not downloaded again.
+#[derive(Clone, Debug, Default)]
+pub struct CacheValidators {
+    /// Sent as `If-None-Match`.
+    pub etag: Option<String>,
+    /// Sent as `If-Modified-Since`.
+    pub last_modified: Option<String>,
+}
+
+/// One fetched document of a possibly paginated feed.
+struct FeedPage {
+    meta: FeedMeta,
+    entries: Vec<FeedItem>,
+    /// Absolute URL of the RFC 5005 `rel="next"` page, if any.
+    next: Option<String>,
+}
+
+/// A fetched feed's details and entries.
+pub type FetchedFeed = (FeedMeta, Vec<FeedItem>);
+
+/// Fetches `feeds` concurrently, at most `jobs` at a time and each conditional
+/// on its validators, returning the results in the same order.
+pub async fn fetch_all(
+    feeds: Vec<(String, CacheValidators)>,
+    jobs: usize,
+    options: FetchOptions,
+) -> Vec<(String, Result<FetchedFeed, FetchError>)> {
+    let permits = Arc::new(Semaphore::new(jobs.max(1)));
+    let tasks: Vec<_> = feeds
+        .into_iter()
+        .map(|(url, validators)| {
+            let permits = Arc::clone(&permits);
+            tokio::spawn(async move {
+                // The semaphore is never closed, so this only waits for a free slot.
+                let _permit = permits.acquire_owned().await;
+                let result = fetch_feed_pages(&url, 1, &validators, &options).await;
+                (url, result)
+            })
+        })
+        .collect();
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        // A fetch only fails to join if it panicked; the others still count.
+        if let Ok(result) = task.await {
+            results.push(result);
+        }
+    }
+    results
+}
+
+/// Fetches a single feed document with the default timeout and retries.
+// Every command passes its configured options; this stays for callers that
+// have none, which today are only the tests.
+#[cfg_attr(not(test), allow(dead_code))]
+pub async fn fetch_feed_items(url: &str) -> Result<(FeedMeta, Vec<FeedItem>), FetchError> {
+    fetch_feed_items_opts(url, &FetchOptions::default()).await
+}
+
+pub async fn fetch_feed_items_opts(
+    url: &str,
+    options: &FetchOptions,
+) -> Result<(FeedMeta, Vec<FeedItem>), FetchError> {
+    fetch_feed_pages(url, 1, &CacheValidators::default(), options).await
+}
+
+/// Fetches a feed and follows its `rel="next"` links for up to `max_pages`
+/// documents in total, dropping entries whose id was already seen. The feed
+/// details come from the first page. With `validators`, the first request is
+/// conditional and an unchanged feed fails with [`FetchError::NotModified`].
+pub async fn fetch_feed_pages(
+    url: &str,
+    max_pages: usize,
+    validators: &CacheValidators,
+    options: &FetchOptions,
+) -> Result<(FeedMeta, Vec<FeedItem>), FetchError> {
+    let first = fetch_page(url, validators, options).await?;
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    let mut add = |entries: Vec<FeedItem>| {
+        for item in entries {
+            if seen.insert(item.id.clone()) {
+                items.push(item);
+            }
+        }
+    };
+    add(first.entries);
+    let mut visited = HashSet::from([url.to_string()]);
+    let mut next = first.next;
+    for _ in 1..max_pages {
+        let Some(page_url) = next.take() else {
+            break;
+        };
+        if !visited.insert(page_url.clone()) {
+            break;
+        }
+        let page = fetch_page(&page_url, &CacheValidators::default(), options).await?;
+        add(page.entries);
+        next = page.next;
+    }
+    Ok((first.meta, items))
+}
 
-pub fn fetch_feed_items(url: &str) -> Result<(Option<String>, Vec<FeedItem>)> {
-    let response = reqwest::blocking::get(url)
-        .with_context(|| format!("failed to fetch feed {}", url))?;
-    let bytes = response.bytes().context("failed to read feed response")?;
-    let feed = feed_rs::parser::parse(bytes.as_ref()).context("failed to parse feed")?;
+/// Date text the timestamp parser could not read. feed-rs drops such dates
+/// without a trace, so the parser hands back a placeholder instead, long
+/// before any real date, which `take` turns back into the text.
+#[derive(Clone, Default)]
+struct RejectedDates(Arc<Mutex<Vec<String>>>);
+
+impl RejectedDates {
+    fn placeholder(&self, text: &str) -> DateTime<Utc> {
+        let mut rejected = self.0.lock().unwrap();
+        rejected.push(text.trim().to_string());
+        DateTime::<Utc>::MIN_UTC + TimeDelta::seconds(rejected.len() as i64 - 1)
+    }
+
+    /// The real date, or the text a placeholder stands for.
+    fn take(&self, date: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        let rejected = self.0.lock().unwrap();
+        let index = (date - DateTime::<Utc>::MIN_UTC).num_seconds();
+        match usize::try_from(index).ok().and_then(|index| rejected.get(index)) {
+            Some(text) => Err(text.clone()),
+            None => Ok(date),
+        }
+    }
+}
+
+async fn fetch_page(
+    url: &str,
+    validators: &CacheValidators,
+    options: &FetchOptions,
+) -> Result<FeedPage, FetchError> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(options.timeout_secs))
+        .build()
+        .map_err(|err| FetchError::request(url, err))?;
+    let request = || {
+        let mut request = client.get(url);
+        if let Some(etag) = &validators.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    };
+    let mut attempt = 0;
+    let response = loop {
+        let result = request().send().await;
+        let transient = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(err) => err.is_timeout() || err.is_connect(),
+        };
+        if !transient || attempt >= options.retries {
+            break result.map_err(|err| FetchError::request(url, err))?;
+        }
+        tokio::time::sleep(options.retry_delay(attempt)).await;
+        attempt += 1;
+    };
+    let status = response.status();
+    let http_status = Some(status.as_u16());
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || header("x-ratelimit-remaining") == Some("0")
+    {
+        return Err(FetchError::RateLimited {
+            url: url.to_string(),
+            retry_after_secs: header("x-ratelimit-reset").and_then(|value| value.parse().ok()),
+        });
+    }
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Err(FetchError::NotModified {
+            url: url.to_string(),
+            validators: CacheValidators {
+                etag: header(reqwest::header::ETAG.as_str()).map(str::to_string),
+                last_modified: header(reqwest::header::LAST_MODIFIED.as_str()).map(str::to_string),
+            },
+        });
+    }
+    if !status.is_success() {
+        return Err(FetchError::Http {
+            url: url.to_string(),
+            status: status.as_u16(),
+        });
+    }
+    let too_large = || FetchError::TooLarge {
+        url: url.to_string(),
+    };
+    if response.content_length().is_some_and(|len| len > MAX_FEED_BYTES) {
+        return Err(too_large());
+    }
+    let content_type = header(reqwest::header::CONTENT_TYPE.as_str()).map(str::to_string);
+    let etag = header(reqwest::header::ETAG.as_str()).map(str::to_string);
+    let last_modified = header(reqwest::header::LAST_MODIFIED.as_str()).map(str::to_string);
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|err| FetchError::request(url, err))?;
+    if bytes.len() as u64 > MAX_FEED_BYTES {
+        return Err(too_large());
+    }
+    let rejected = RejectedDates::default();
+    let parser = {
+        let rejected = rejected.clone();
+        feed_rs::parser::Builder::new()
+            .timestamp_parser(move |text| {
+                let date = parse_date_fallback(text).map(|date| date.with_timezone(&Utc));
+                Some(date.unwrap_or_else(|| rejected.placeholder(text)))
+            })
+            .build()
+    };
+    let feed = parser.parse(bytes.as_ref()).map_err(|source| match source {
+        ParseFeedError::ParseError(ParseErrorKind::NoFeedRoot) => FetchError::NotAFeed {
+            url: url.to_string(),
+        },
+        source => FetchError::Parse {
+            url: url.to_string(),
+            location: error_offset(&source, &bytes)
+                .map(|offset| describe_offset(&bytes, offset))
+                .unwrap_or_default(),
+            source,
+        },
+    })?;
     let title = feed.title.map(|text| text.content);
-    let items = feed
+    let description = feed.description.map(|text| text.content);
+    let next = feed
+        .links
+        .iter()
+        .find(|link| link.rel.as_deref() == Some("next"))
+        .map(|link| match reqwest::Url::parse(url).and_then(|base| base.join(&link.href)) {
+            Ok(next) => next.to_string(),
+            Err(_) => link.href.clone(),
+        });
+    let entries = feed
         .entries
         .into_iter()
-        .map(|entry| FeedItem {
-            title: entry
+        .map(|entry| {
+            let link = entry.links.first().map(|link| link.href.clone());
+            let (published, published_raw) = match entry.published.map(|d| rejected.take(d)) {
+                Some(Ok(date)) => (Some(date.to_rfc3339()), None),
+                Some(Err(text)) => (None, Some(text)),
+                None => (None, None),
+            };
+            let published_error = published_raw.as_deref().map(describe_date_error);
+            let title = entry
                 .title
                 .as_ref()
-                .map(|text| text.content.clone())
-                .unwrap_or_else(|| "Untitled".to_string()),
-            link: entry.links.first().map(|link| link.href.clone()),
-            published: entry.published.map(|date| date.to_rfc3339()),
+                .map(|text| text.content.trim())
+                .filter(|title| !is_placeholder_title(title));
+            let (title, title_synthesized) = match title {
+                Some(title) => (title.to_string(), false),
+                None => {
+                    let summary = entry
+                        .summary
+                        .as_ref()
+                        .map(|text| text.content.as_str())
+                        .or_else(|| entry.content.as_ref().and_then(|c| c.body.as_deref()));
+                    let title = synthesize_title(summary, link.as_deref(), published.as_deref());
+                    (title, true)
+                }
+            };
+            let summary = entry
+                .summary
+                .as_ref()
+                .map(|text| text.content.as_str())
+                .or_else(|| entry.content.as_ref().and_then(|c| c.body.as_deref()))
+                .map(html_to_text)
+                .filter(|text| !text.is_empty());
+            let author = entry
+                .authors
+                .first()
+                .map(|person| person.name.trim().to_string())
+                .filter(|name| !name.is_empty());
+            FeedItem {
+                id: Some(entry.id),
+                title,
+                link,
+                published,
+                published_raw,
+                published_error,
+                title_synthesized,
+                read: false,
+                author,
+                summary,
+                missing_since: None,
+            }
         })
         .collect();
-    Ok((title, items))
+    let meta = FeedMeta {
+        title,
+        description,
+        content_type,
+        http_status,
+        etag,
+        last_modified,
+    };
+    Ok(FeedPage {
+        meta,
+        entries,
+        next,
+    })
+}
+
+/// Characters of context shown on each side of a parse error.
+const SNIPPET_CHARS: usize = 30;
+
+/// Byte offset in `body` where parsing failed. feed-rs does not report one,
+/// so XML is re-read with quick-xml, which stops at the same malformation.
+fn error_offset(error: &ParseFeedError, body: &[u8]) -> Option<usize> {
+    match error {
+        ParseFeedError::JsonSerde(err) if err.line() > 0 => {
+            let line_start = body
+                .split_inclusive(|&byte| byte == b'\n')
+                .take(err.line() - 1)
+                .map(<[u8]>::len)
+                .sum::<usize>();
+            Some((line_start + err.column().saturating_sub(1)).min(body.len()))
+        }
+        ParseFeedError::XmlReader(_) => {
+            let mut reader = quick_xml::Reader::from_reader(body);
+            reader.expand_empty_elements(true).trim_markup_names_in_closing_tags(true);
+            let mut buf = Vec::new();
+            loop {
+                match reader.read_event_into(&mut buf) {
+                    Ok(quick_xml::events::Event::Eof) => return None,
+                    Ok(_) => buf.clear(),
+                    Err(_) => return Some(reader.buffer_position().min(body.len())),
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Describes a position in `body` as its line number and the text around it.
+fn describe_offset(body: &[u8], offset: usize) -> String {
+    let line = body[..offset].iter().filter(|&&byte| byte == b'\n').count() + 1;
+    format!(" at line {line}, near `{}`", snippet_at(body, offset))
+}
+
+/// A short, single-line excerpt of `body` around `offset`. The window is
+/// widened to whole UTF-8 characters, invalid bytes are replaced, and control
+/// characters and runs of whitespace become single spaces.
+fn snippet_at(body: &[u8], offset: usize) -> String {
+    let offset = offset.min(body.len());
+    let is_continuation = |byte: u8| byte & 0b1100_0000 == 0b1000_0000;
+    let mut start = offset;
+    let mut chars = 0;
+    while start > 0 && chars < SNIPPET_CHARS {
+        start -= 1;
+        if !is_continuation(body[start]) {
+            chars += 1;
+        }
+    }
+    let mut end = offset;
+    chars = 0;
+    while end < body.len() && chars < SNIPPET_CHARS {
+        end += 1;
+        while end < body.len() && is_continuation(body[end]) {
+            end += 1;
+        }
+        chars += 1;
+    }
+    let text = String::from_utf8_lossy(&body[start..end]);
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut last_space = false;
+    for ch in text.chars() {
+        let ch = if ch.is_control() || ch.is_whitespace() { ' ' } else { ch };
+        if ch == ' ' && last_space {
+            continue;
+        }
+        last_space = ch == ' ';
+        snippet.push(ch);
+    }
+    if end < body.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Reduces a feed URL to a form where trivially different spellings of the
+/// same address compare equal: scheme, default port, fragment, trailing slash
+/// and host case are ignored.
+pub fn normalize_feed_url(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split_once('#').map_or(url, |(before, _)| before);
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (host, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    let host = host.to_ascii_lowercase();
+    let host = host
+        .strip_suffix(":80")
+        .or_else(|| host.strip_suffix(":443"))
+        .unwrap_or(&host);
+    format!("{host}{}", path.trim_end_matches('/'))
+}
+
+fn is_placeholder_title(title: &str) -> bool {
+    let title = title.trim();
+    title.is_empty() || title.eq_ignore_ascii_case("untitled")
+}
+
+/// Makes up a display title for an entry without one: the first non-empty
+/// line of its summary, else the last segment of its link's path, else
+/// "Untitled (<date>)".
+pub fn synthesize_title(
+    summary: Option<&str>,
+    link: Option<&str>,
+    published: Option<&str>,
+) -> String {
+    let from_summary = summary.map(html_to_text).and_then(|text| {
+        let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut title: String = line.chars().take(SYNTHESIZED_TITLE_CHARS).collect();
+        if line.chars().count() > SYNTHESIZED_TITLE_CHARS {
+            title.push('…');
+        }
+        Some(title)
+    });
+    if let Some(title) = from_summary {
+        return title;
+    }
+    if let Some(segment) = link.and_then(last_path_segment) {
+        return segment;
+    }
+    match published.and_then(|date| date.get(..10)) {
+        Some(date) => format!("Untitled ({date})"),
+        None => "Untitled".to_string(),
+    }
+}
+
+/// Tags that start a new line of text when an entry's HTML is flattened.
+const BLOCK_TAGS: &[&str] = &[
+    "p", "br", "div", "li", "ul", "ol", "tr", "blockquote", "pre", "h1", "h2", "h3", "h4", "h5",
+    "h6", "hr", "table", "section", "article", "header", "footer", "figure", "figcaption",
+];
+
+/// Flattens an entry's summary or content to readable plain text: tags are
+/// dropped, block elements become line breaks, `script` and `style` bodies
+/// are skipped, entities are decoded and runs of whitespace and blank lines
+/// are collapsed. Plain text passes through unchanged apart from that.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        let tag = &rest[start + 1..];
+        // A `<` that does not open a tag, as in "a < b", is ordinary text.
+        if !tag.starts_with(|ch: char| ch.is_ascii_alphabetic() || matches!(ch, '/' | '!' | '?')) {
+            text.push('<');
+            rest = tag;
+            continue;
+        }
+        let Some(end) = tag.find('>') else {
+            rest = "";
+            break;
+        };
+        let name: String = tag[..end]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        rest = &tag[end + 1..];
+        if (name == "script" || name == "style") && !tag.starts_with('/') {
+            let close = format!("</{name}");
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(index) => &rest[index..],
+                None => "",
+            };
+        } else if BLOCK_TAGS.contains(&name.as_str()) {
+            text.push('\n');
+        }
+    }
+    text.push_str(&decode_entities(rest));
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Decodes the XML entities, numeric references and the named HTML entities
+/// feeds commonly use; anything else is left as written.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end + 1])?, end + 2)));
+        match entity {
+            Some((ch, len)) => {
+                decoded.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "copy" => '\u{a9}',
+        _ => return None,
+    })
+}
+
+/// Turns `https://example.com/posts/hello-world.html?x=1` into "hello world".
+fn last_path_segment(link: &str) -> Option<String> {
+    let rest = link.split_once("://").map_or(link, |(_, rest)| rest);
+    let path = &rest[rest.find('/')?..];
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let segment = path.trim_end_matches('/').rsplit('/').next()?;
+    let segment = segment.rsplit_once('.').map_or(segment, |(stem, _)| stem);
+    let words = segment.replace(['-', '_'], " ");
+    let words = words.trim();
+    (!words.is_empty()).then(|| words.to_string())
+}
+
+/// Checks parsed items for problems that degrade sorting and dedup later on.
+/// Never fails; each returned string is a human-readable warning. Dates are
+/// judged against `now`.
+pub fn validate_items(items: &[FeedItem], now: DateTime<Utc>) -> Vec<String> {
+    let total = items.len();
+    let mut warnings = Vec::new();
+    if total == 0 {
+        return warnings;
+    }
+
+    let undated = items
+        .iter()
+        .filter(|item| item.published.as_deref().is_none_or(str::is_empty))
+        .count();
+    if undated > 0 {
+        warnings.push(format!(
+            "{undated} of {total} entries have no published date; sorting will be unreliable for this feed"
+        ));
+    }
+
+    let mut dates: Vec<_> = items
+        .iter()
+        .filter_map(|item| parse_published(item.published.as_deref()))
+        .collect();
+    dates.sort();
+    if let Some(median) = dates.get(dates.len() / 2)
+        && *median > now
+    {
+        warnings.push(format!(
+            "most entries are dated in the future (median {}); the feed's clock may be wrong",
+            median.to_rfc3339()
+        ));
+    }
+
+    let untitled = items
+        .iter()
+        .filter(|item| item.title_synthesized || is_placeholder_title(&item.title))
+        .count();
+    if untitled > 0 {
+        warnings.push(format!("{untitled} of {total} entries have no title"));
+    }
+
+    let mut seen = HashSet::new();
+    let duplicates = items
+        .iter()
+        .filter_map(|item| item.id.as_deref())
+        .filter(|id| !seen.insert(*id))
+        .count();
+    if duplicates > 0 {
+        warnings.push(format!("{duplicates} of {total} entries repeat another entry's GUID"));
+    }
+
+    let mut seen = HashSet::new();
+    let duplicates = items
+        .iter()
+        .filter_map(|item| item.link.as_deref())
+        .filter(|link| !seen.insert(*link))
+        .count();
+    if duplicates > 0 {
+        warnings.push(format!("{duplicates} of {total} entries repeat another entry's link"));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::format::explain_published;
+    use crate::test_util::{conditional_server, item, response, rss, serve};
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Runtime::new().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn default_fetch_reads_a_feed() {
+        let url = serve(|_| response("200 OK", &[], &rss(&["one", "two"])));
+        let (meta, items) = block_on(fetch_feed_items(&url)).unwrap();
+
+        assert_eq!(meta.title.as_deref(), Some("Example"));
+        assert_eq!(meta.http_status, Some(200));
+        let titles: Vec<_> = items.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, ["one", "two"]);
+    }
+
+    #[test]
+    fn unparseable_dates_keep_their_text_and_reason() {
+        let feed = "<rss version=\"2.0\"><channel><title>Example</title>\
+            <item><title>bad</title><pubDate>Mon, 32 Jan 2024 10:00:00 +0000</pubDate></item>\
+            <item><title>good</title><pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate></item>\
+            <item><title>nonsense</title><pubDate>last week</pubDate></item>\
+            </channel></rss>";
+        let url = serve(move |_| response("200 OK", &[], feed));
+        let (_, items) = block_on(fetch_feed_items(&url)).unwrap();
+
+        assert_eq!(items[0].published, None);
+        assert_eq!(items[0].published_raw.as_deref(), Some("Mon, 32 Jan 2024 10:00:00 +0000"));
+        assert_eq!(items[0].published_error.as_deref(), Some("input is out of range"));
+        assert_eq!(items[1].published.as_deref(), Some("2024-01-02T10:00:00+00:00"));
+        assert_eq!(items[1].published_raw, None);
+        assert_eq!(items[2].published_raw.as_deref(), Some("last week"));
+        assert!(explain_published(&items[2]).starts_with("unparseable date \"last week\""));
+    }
+
+    #[test]
+    fn missing_page_is_an_http_error() {
+        let url = serve(|_| response("404 Not Found", &[], "gone"));
+        let err = block_on(fetch_feed_items(&url)).unwrap_err();
+
+        assert!(matches!(err, FetchError::Http { status: 404, .. }), "{err:?}");
+        assert_eq!(err.http_status(), Some(404));
+    }
+
+    #[test]
+    fn html_page_is_not_a_feed() {
+        let url = serve(|_| response("200 OK", &[], "<html><body>Hello</body></html>"));
+        let err = block_on(fetch_feed_items(&url)).unwrap_err();
+        assert!(matches!(err, FetchError::NotAFeed { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn matching_etag_is_not_modified() {
+        let url = conditional_server("if-none-match", "\"v1\"");
+        let (meta, items) = block_on(fetch_feed_items(&url)).unwrap();
+        assert_eq!(meta.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(items.len(), 1);
+
+        let validators = CacheValidators {
+            etag: meta.etag,
+            last_modified: None,
+        };
+        let options = FetchOptions::default();
+        match block_on(fetch_feed_pages(&url, 1, &validators, &options)) {
+            Err(FetchError::NotModified { validators, .. }) => {
+                assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+            }
+            other => panic!("expected not modified, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn changed_etag_fetches_again() {
+        let url = conditional_server("if-none-match", "\"v1\"");
+        let validators = CacheValidators {
+            etag: Some("\"v0\"".to_string()),
+            last_modified: None,
+        };
+        let result = block_on(fetch_feed_pages(&url, 1, &validators, &FetchOptions::default()));
+        assert_eq!(result.unwrap().1.len(), 1);
+    }
+
+    #[test]
+    fn matching_last_modified_is_not_modified() {
+        let url = conditional_server("if-modified-since", "Mon, 01 Jan 2024 00:00:00 GMT");
+        let (meta, _) = block_on(fetch_feed_items(&url)).unwrap();
+        let validators = CacheValidators {
+            etag: None,
+            last_modified: meta.last_modified,
+        };
+        let result = block_on(fetch_feed_pages(&url, 1, &validators, &FetchOptions::default()));
+        assert!(matches!(result, Err(FetchError::NotModified { .. })), "{result:?}");
+    }
+
+    #[test]
+    fn retry_delays_double_from_the_base() {
+        let options = FetchOptions {
+            retry_base_ms: 500,
+            ..FetchOptions::default()
+        };
+        let delays: Vec<_> = (0..4).map(|attempt| options.retry_delay(attempt)).collect();
+        assert_eq!(delays, [500, 1000, 2000, 4000].map(Duration::from_millis));
+        assert_eq!(options.retry_delay(u8::MAX), Duration::from_millis(u64::MAX));
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+        let url = serve(|_| match REQUESTS.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => response("503 Service Unavailable", &[], ""),
+            _ => response("200 OK", &[], &rss(&["one"])),
+        });
+        let options = FetchOptions {
+            retries: 2,
+            retry_base_ms: 1,
+            ..FetchOptions::default()
+        };
+        let (_, items) = block_on(fetch_feed_items_opts(&url, &options)).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn retries_give_up_with_the_last_status() {
+        let url = serve(|_| response("502 Bad Gateway", &[], ""));
+        let options = FetchOptions {
+            retries: 1,
+            retry_base_ms: 1,
+            ..FetchOptions::default()
+        };
+        let err = block_on(fetch_feed_items_opts(&url, &options)).unwrap_err();
+        assert!(matches!(err, FetchError::Http { status: 502, .. }), "{err:?}");
+    }
+
+    fn parse_error(body: &[u8]) -> ParseFeedError {
+        feed_rs::parser::parse(body).expect_err("the body should not parse")
+    }
+
+    #[test]
+    fn synthesized_title_prefers_the_first_summary_line() {
+        let summary = "<p>  </p><p>First   <b>real</b> line</p><p>Second</p>";
+        let title = synthesize_title(Some(summary), Some("https://example.com/a-post"), None);
+        assert_eq!(title, "First real line");
+    }
+
+    #[test]
+    fn synthesized_title_truncates_long_summaries() {
+        let summary = "é".repeat(SYNTHESIZED_TITLE_CHARS + 5);
+        let title = synthesize_title(Some(&summary), None, None);
+        assert_eq!(title, format!("{}…", "é".repeat(SYNTHESIZED_TITLE_CHARS)));
+
+        let exact = "a".repeat(SYNTHESIZED_TITLE_CHARS);
+        assert_eq!(synthesize_title(Some(&exact), None, None), exact);
+    }
+
+    #[test]
+    fn synthesized_title_falls_back_to_the_link_path() {
+        let link = Some("https://example.com/posts/hello-world_again.html?ref=rss#top");
+        assert_eq!(synthesize_title(Some("<p> </p>"), link, None), "hello world again");
+        assert_eq!(synthesize_title(None, Some("https://example.com/posts/"), None), "posts");
+    }
+
+    #[test]
+    fn synthesized_title_falls_back_to_the_date() {
+        let published = Some("2024-03-05T10:00:00+00:00");
+        assert_eq!(
+            synthesize_title(None, Some("https://example.com/"), published),
+            "Untitled (2024-03-05)"
+        );
+        assert_eq!(synthesize_title(Some(""), None, None), "Untitled");
+    }
+
+    #[test]
+    fn placeholder_titles_are_synthesized_on_fetch() {
+        let body = "<rss version=\"2.0\"><channel><title>Example</title>\
+                    <item><title>  Untitled </title>\
+                    <link>https://example.com/first-post</link></item>\
+                    <item><title></title>\
+                    <description>&lt;p&gt;From the summary&lt;/p&gt;</description></item>\
+                    <item><title>Real</title></item>\
+                    </channel></rss>";
+        let url = serve(move |_| response("200 OK", &[], body));
+        let (_, items) = block_on(fetch_feed_items(&url)).unwrap();
+
+        let titles: Vec<_> = items
+            .iter()
+            .map(|item| (item.title.as_str(), item.title_synthesized))
+            .collect();
+        assert_eq!(titles, [("first post", true), ("From the summary", true), ("Real", false)]);
+    }
+
+    #[test]
+    fn validation_flags_each_kind_of_breakage() {
+        let entry = |id: &str, title: &str, published: Option<&str>| FeedItem {
+            id: Some(id.to_string()),
+            published: published.map(str::to_string),
+            ..item(title, &format!("https://example.com/{id}"))
+        };
+        let date = Some("2024-01-01T00:00:00+00:00");
+        let now = DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z").unwrap().to_utc();
+        let cases: [(&str, Vec<FeedItem>, &[&str]); 5] = [
+            ("clean", vec![entry("1", "One", date), entry("2", "Two", date)], &[]),
+            ("empty", Vec::new(), &[]),
+            (
+                "duplicate GUIDs",
+                vec![
+                    entry("1", "One", date),
+                    FeedItem {
+                        link: Some("https://example.com/2".to_string()),
+                        ..entry("1", "Two", date)
+                    },
+                ],
+                &["1 of 2 entries repeat another entry's GUID"],
+            ),
+            (
+                "all undated",
+                vec![entry("1", "One", None), entry("2", "Two", None)],
+                &["2 of 2 entries have no published date; \
+                   sorting will be unreliable for this feed"],
+            ),
+            (
+                "untitled",
+                vec![
+                    entry("1", "", date),
+                    entry("2", "Untitled", date),
+                    FeedItem {
+                        title_synthesized: true,
+                        ..entry("3", "Made up", date)
+                    },
+                    entry("4", "Four", date),
+                ],
+                &["3 of 4 entries have no title"],
+            ),
+        ];
+        for (name, items, expected) in cases {
+            assert_eq!(validate_items(&items, now), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn future_median_is_judged_against_now() {
+        let dated = |published: &str| FeedItem {
+            published: Some(published.to_string()),
+            ..item("Entry", "https://example.com/entry")
+        };
+        let items = [
+            dated("2024-01-01T00:00:00+00:00"),
+            dated("2038-01-01T00:00:00+00:00"),
+            dated("2038-02-01T00:00:00+00:00"),
+        ];
+        let at = |date: &str| DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc);
+        let future = |warnings: Vec<String>| warnings.iter().any(|w| w.contains("in the future"));
+
+        assert!(future(validate_items(&items, at("2024-06-01T00:00:00Z"))));
+        assert!(!future(validate_items(&items, at("2038-01-15T00:00:00Z"))));
+    }
+
+    #[test]
+    fn error_offset_lands_on_a_character_boundary() {
+        let body = "<rss version=\"2.0\"><channel>\n\
+                    <title>Le café</title>\n\
+                    <item></chanel>\n</rss>";
+        let body = body.as_bytes();
+        let offset = error_offset(&parse_error(body), body).expect("an XML error has an offset");
+
+        assert!(std::str::from_utf8(&body[..offset]).is_ok());
+        let described = describe_offset(body, offset);
+        assert!(described.starts_with(" at line 3, near `"), "{described}");
+        assert!(described.contains("café"), "{described}");
+    }
+
+    #[test]
+    fn describe_offset_counts_lines() {
+        let body = b"one\ntwo\nthree";
+        assert_eq!(describe_offset(body, 0), " at line 1, near `one two three`");
+        assert_eq!(describe_offset(body, 4), " at line 2, near `one two three`");
+        assert_eq!(describe_offset(body, body.len()), " at line 3, near `one two three`");
+    }
+
+    #[test]
+    fn snippet_widens_an_offset_inside_a_character() {
+        let body = "<title>café</title>".as_bytes();
+        let inside_e = body.iter().position(|&byte| byte == 0xC3).unwrap() + 1;
+        assert_eq!(snippet_at(body, inside_e), "<title>café</title>");
+    }
+
+    #[test]
+    fn truncation_never_splits_a_character() {
+        let body = "é".repeat(100);
+        let body = body.as_bytes();
+        assert_eq!(snippet_at(body, 100), format!("…{}…", "é".repeat(60)));
+
+        let snippet = snippet_at(body, 101);
+        assert!(!snippet.contains('\u{FFFD}'), "{snippet}");
+        assert_eq!(snippet, format!("…{}…", "é".repeat(59)));
+    }
 }
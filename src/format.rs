@@ -0,0 +1,230 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::db::FeedItem;
+
+/// Date-only layouts seen in the wild in `<pubDate>` and friends.
+const DATE_FORMATS: &[&str] = &[
+    "%a %b %d %Y",
+    "%a, %b %d %Y",
+    "%a, %d %b %Y",
+    "%d %b %Y",
+    "%b %d %Y",
+    "%b %d, %Y",
+    "%B %d %Y",
+    "%B %d, %Y",
+    "%d %B %Y",
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%d/%m/%Y",
+    "%d.%m.%Y",
+];
+
+/// Date and time layouts without an offset; these are taken to be UTC.
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%a, %d %b %Y %H:%M:%S",
+    "%a, %d %b %Y %H:%M",
+    "%d %b %Y %H:%M:%S",
+    "%a %b %d %H:%M:%S %Y",
+];
+
+/// Date and time layouts that carry a numeric offset.
+const OFFSET_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%z",
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S %z",
+    "%Y-%m-%d %H:%M:%S%z",
+    "%a, %d %b %Y %H:%M %z",
+    "%a %b %d %Y %H:%M:%S %z",
+];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parses a feed timestamp, accepting RFC 3339 and RFC 2822 as well as a
+/// handful of common non-standard layouts. Values without an offset are
+/// read as UTC; the result is always converted to UTC.
+///
+/// This replaces feed-rs's own lenient parser, so it repairs the same RFC 2822
+/// mistakes that one does before trying the extra layouts.
+pub fn parse_date_fallback(s: &str) -> Option<DateTime<FixedOffset>> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    let utc = |date: DateTime<FixedOffset>| date.with_timezone(&Utc).fixed_offset();
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Some(utc(date));
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(s) {
+        return Some(utc(date));
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(&repair_rfc2822(s)) {
+        return Some(utc(date));
+    }
+    // Treat a trailing "GMT"/"UTC" as an explicit zero offset.
+    let s = s
+        .strip_suffix(" GMT")
+        .or_else(|| s.strip_suffix(" UTC"))
+        .unwrap_or(s);
+    for format in OFFSET_FORMATS {
+        if let Ok(date) = DateTime::parse_from_str(s, format) {
+            return Some(utc(date));
+        }
+    }
+    for format in DATETIME_FORMATS {
+        if let Ok(date) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(Utc.from_utc_datetime(&date).fixed_offset());
+        }
+    }
+    for format in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(s, format) {
+            return Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?).fixed_offset());
+        }
+    }
+    None
+}
+
+/// Rewrites the RFC 2822 mistakes feeds commonly make: a weekday that is
+/// wrong, long ("Thurs") or localized is dropped, long month names are
+/// shortened, single-digit hours are padded, hour 24 becomes 00 on the same
+/// day, and "UTC", "Z" or "-0000" become "+0000".
+fn repair_rfc2822(s: &str) -> String {
+    let mut words: Vec<String> = s.split_whitespace().map(str::to_string).collect();
+    if words
+        .first()
+        .and_then(|word| word.strip_suffix(','))
+        .is_some_and(|day| day.chars().all(char::is_alphabetic))
+    {
+        words.remove(0);
+    }
+    for word in &mut words {
+        if let Some(month) = MONTHS.iter().find(|month| {
+            word.strip_prefix(**month)
+                .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_lowercase()))
+        }) {
+            *word = month.to_string();
+        } else if let Some((hour, rest)) = word.split_once(':') {
+            let hour = match hour {
+                "24" => "00".to_string(),
+                hour if hour.len() == 1 && hour.chars().all(|c| c.is_ascii_digit()) => {
+                    format!("0{hour}")
+                }
+                hour => hour.to_string(),
+            };
+            *word = format!("{hour}:{rest}");
+        } else if matches!(word.as_str(), "UTC" | "Z" | "-0000") {
+            *word = "+0000".to_string();
+        }
+    }
+    words.join(" ")
+}
+
+pub fn days_ago(days: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+    now - Duration::days(i64::from(days))
+}
+
+pub fn hours_ago(hours: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+    now - Duration::hours(i64::from(hours))
+}
+
+/// Why `parse_date_fallback` rejected `s`, as chrono words it: RFC 3339's
+/// complaint for text that starts with a year, RFC 2822's otherwise.
+pub fn describe_date_error(s: &str) -> String {
+    let s = s.trim();
+    let err = if s.get(..4).is_some_and(|year| year.bytes().all(|b| b.is_ascii_digit())) {
+        DateTime::parse_from_rfc3339(s).err()
+    } else {
+        DateTime::parse_from_rfc2822(&repair_rfc2822(s)).err()
+    };
+    err.map_or_else(|| "unsupported layout".to_string(), |err| err.to_string())
+}
+
+/// Describes the key an item sorts by, including why its date could not be
+/// used, for `db --explain-sort`.
+pub fn explain_published(item: &FeedItem) -> String {
+    if let Some(raw) = &item.published_raw {
+        let reason = item.published_error.as_deref().unwrap_or("unsupported layout");
+        return format!("unparseable date {raw:?}: {reason} (sorts last)");
+    }
+    match item.published.as_deref() {
+        None | Some("") => "no date (sorts last)".to_string(),
+        Some(date) => match DateTime::parse_from_rfc3339(date) {
+            Ok(key) => key.with_timezone(&Utc).to_rfc3339(),
+            Err(err) => format!("unparseable date {date:?}: {err} (sorts last)"),
+        },
+    }
+}
+
+/// Parses a stored `published` value, which is always RFC 3339.
+pub fn parse_published(value: Option<&str>) -> Option<DateTime<FixedOffset>> {
+    value.and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::item;
+
+    #[test]
+    fn parses_what_feed_rs_parses_and_more() {
+        let cases = [
+            ("2024-01-01T09:00:00Z", "2024-01-01T09:00:00+00:00"),
+            ("2024-01-01T00:00:00+0000", "2024-01-01T00:00:00+00:00"),
+            ("2024-01-01T10:00:00.5+0100", "2024-01-01T09:00:00.500+00:00"),
+            ("Mon, 01 Jan 2024 09:00:00 +0000", "2024-01-01T09:00:00+00:00"),
+            ("Thurs, 13 Jul 2011 07:38:00 GMT", "2011-07-13T07:38:00+00:00"),
+            ("Mon, 1 Jan 2024 9:00:00 +0000", "2024-01-01T09:00:00+00:00"),
+            ("Tue, 02 January 2024 10:00:00 +0000", "2024-01-02T10:00:00+00:00"),
+            ("Sat, 06 Jan 2024 24:10:00 +0000", "2024-01-06T00:10:00+00:00"),
+            // 2024-01-03 was a Wednesday.
+            ("Fri, 03 Jan 2024 10:00:00 +0100", "2024-01-03T09:00:00+00:00"),
+            ("Wed, 03 Jan 2024 10:00:00 UTC", "2024-01-03T10:00:00+00:00"),
+            ("03 Jan 2024 10:00:00 -0000", "2024-01-03T10:00:00+00:00"),
+            ("Mon Jan 15 2024", "2024-01-15T00:00:00+00:00"),
+            ("2024/01/15", "2024-01-15T00:00:00+00:00"),
+            ("January 15, 2024", "2024-01-15T00:00:00+00:00"),
+            ("2024-01-15 08:30", "2024-01-15T08:30:00+00:00"),
+        ];
+        for (input, expected) in cases {
+            let parsed = parse_date_fallback(input).map(|date| date.to_rfc3339());
+            assert_eq!(parsed.as_deref(), Some(expected), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn explains_a_date_the_feed_got_wrong() {
+        let raw = "Mon, 32 Jan 2024 10:00:00 +0000";
+        let broken = FeedItem {
+            published_raw: Some(raw.to_string()),
+            published_error: Some(describe_date_error(raw)),
+            ..item("Broken", "https://example.com/broken")
+        };
+        assert_eq!(
+            explain_published(&broken),
+            format!("unparseable date {raw:?}: input is out of range (sorts last)")
+        );
+        assert_eq!(describe_date_error("2024-13-01"), "premature end of input");
+        assert_eq!(describe_date_error("yesterday"), "input contains invalid characters");
+
+        let dated = FeedItem {
+            published: Some("2024-01-01T10:00:00+01:00".to_string()),
+            ..item("Dated", "https://example.com/dated")
+        };
+        assert_eq!(explain_published(&dated), "2024-01-01T09:00:00+00:00");
+        assert_eq!(explain_published(&item("Undated", "")), "no date (sorts last)");
+    }
+
+    #[test]
+    fn rejects_text_that_is_not_a_date() {
+        for input in ["", "  ", "yesterday", "Mon, 32 Jan 2024 10:00:00 +0000", "2024-13-01"] {
+            assert_eq!(parse_date_fallback(input), None, "{input:?}");
+        }
+    }
+}
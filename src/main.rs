@@ -1,16 +1,31 @@
+mod api;
 mod db;
+mod export;
 mod feed;
+mod format;
+mod opml;
+mod output;
 mod render;
+mod rollup;
+#[cfg(test)]
+mod test_util;
 mod tui;
 
 use std::path::PathBuf;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use clap::{Args, Parser, Subcommand};
+
+/// Exit status of `fetch --since-etag` when the server reports no change.
+const NOT_MODIFIED_EXIT_CODE: i32 = 3;
 
 #[derive(Parser)]
 #[command(name = "rss-cli", version, about = "Simple CLI RSS reader")]
 struct Cli {
+    /// Emit machine-readable JSON on stdout (errors go to stderr as JSON).
+    #[arg(long, global = true)]
+    json: bool,
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -25,37 +40,555 @@ enum Command {
         /// Only show entries for a specific feed URL.
         #[arg(long)]
         feed: Option<String>,
+        /// Print one `<url> | <title>` line per feed instead of entries.
+        #[arg(long)]
+        feeds_as_list: bool,
+        /// List feeds whose newest item is older than this many days, or that have no items.
+        #[arg(long, value_name = "DAYS", conflicts_with_all = ["feeds_as_list", "since"])]
+        inactive_since: Option<u32>,
+        /// Print database statistics as a single JSON object.
+        #[arg(long, conflicts_with_all = ["feed", "feeds_as_list", "inactive_since"])]
+        stats_json: bool,
+        /// Print only the link of each entry, one per line; entries without links are skipped.
+        #[arg(long, conflicts_with_all = ["feeds_as_list", "inactive_since", "stats_json"])]
+        url_only: bool,
+        /// Append the date each entry sorts by, or why its date could not be used.
+        #[arg(long)]
+        explain_sort: bool,
+        /// Print each entry's summary, indented, under its line.
+        #[arg(long, conflicts_with_all = ["feeds_as_list", "inactive_since", "stats_json"])]
+        show_summary: bool,
+        /// Sort the --feed's entries by date from now on, instead of keeping the feed's order.
+        #[arg(long, requires = "feed")]
+        sort_by_date: bool,
+        /// Show the --feed's entries in the order the feed lists them again.
+        #[arg(long, requires = "feed", conflicts_with = "sort_by_date")]
+        preserve_order: bool,
+        /// Only show entries published in the last N days.
+        #[arg(long, value_name = "N", group = "since")]
+        last_days: Option<u32>,
+        /// Only show entries published in the last N hours.
+        #[arg(long, value_name = "N", group = "since")]
+        last_hours: Option<u32>,
+        /// Same as --last-days 7.
+        #[arg(long, group = "since")]
+        last_week: bool,
+        /// Same as --last-days 30.
+        #[arg(long, group = "since")]
+        last_month: bool,
+        /// Same as --last-days 365.
+        #[arg(long, group = "since")]
+        last_year: bool,
     },
     /// Fetch and display entries directly from a feed URL.
     Fetch {
         /// Feed URL to retrieve.
         #[arg(long)]
         url: String,
+        /// Follow `rel="next"` links, fetching at most this many pages in total.
+        #[arg(
+            long,
+            value_name = "MAX",
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        follow_pages: u32,
+        /// Send `If-None-Match` with this ETag; exit with status 3 and no output if unchanged.
+        #[arg(long, value_name = "ETAG")]
+        since_etag: Option<String>,
+        /// Save the fetched feed to this database file instead of printing it.
+        #[arg(long)]
+        output_db: Option<PathBuf>,
+        /// Add the feed to the existing database rather than replacing its contents.
+        #[arg(long, requires = "output_db")]
+        append: bool,
+        /// Create the output database's parent directory if it does not exist.
+        #[arg(long, requires = "output_db")]
+        create_dirs: bool,
+        /// Replace the stored feed title even if it was updated recently.
+        #[arg(long, requires = "append")]
+        refresh_metadata: bool,
+        /// Days a stored feed title is kept before a fetch replaces it.
+        #[arg(
+            long,
+            value_name = "DAYS",
+            requires = "append",
+            default_value_t = db::DEFAULT_METADATA_INTERVAL_DAYS
+        )]
+        metadata_interval_days: u32,
+        /// Which existing entries to store if the feed is new to the database:
+        /// all, none, or days:N for those published in the last N days. Defaults
+        /// to the database's `backfill` setting.
+        #[arg(long, value_name = "POLICY", requires = "output_db")]
+        backfill: Option<db::Backfill>,
+        /// Print each entry's summary, indented, under its line.
+        #[arg(long, conflicts_with = "output_db")]
+        show_summary: bool,
+        #[command(flatten)]
+        fetch_args: FetchArgs,
+    },
+    /// Subscribe to the feeds listed in an OPML file without fetching them.
+    Import {
+        /// OPML file to read.
+        #[arg(long, visible_alias = "opml")]
+        path: PathBuf,
+        /// Database file to add the feeds to (.json, .yml, .yaml).
+        #[arg(long, default_value = "feeds.json")]
+        db: PathBuf,
+        /// Create the database's parent directory if it does not exist.
+        #[arg(long)]
+        create_dirs: bool,
+        /// Also fetch the entries of every imported feed.
+        #[arg(long)]
+        fetch: bool,
+        /// Which entries the first fetch of each imported feed stores: all, none,
+        /// or days:N. Defaults to the database's `backfill` setting.
+        #[arg(long, value_name = "POLICY")]
+        backfill: Option<db::Backfill>,
+        /// Feeds fetched at the same time with --fetch.
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = feed::DEFAULT_JOBS,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        jobs: u32,
+        #[command(flatten)]
+        fetch_args: FetchArgs,
+    },
+    /// Write the subscribed feeds to a file for another reader.
+    Export {
+        /// File to write.
+        #[arg(long)]
+        path: PathBuf,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = export::ExportFormat::Opml)]
+        format: export::ExportFormat,
+        /// Database file to read (.json, .yml, .yaml).
+        #[arg(long, default_value = "feeds.json")]
+        db: PathBuf,
+    },
+    /// Answer newline-delimited JSON requests on stdin, one JSON line per request on stdout.
+    Api {
+        /// Path to the database file (.json, .yml, .yaml).
+        #[arg(long, default_value = "feeds.json")]
+        db: PathBuf,
+        #[command(flatten)]
+        fetch_args: FetchArgs,
     },
     /// Start a full-screen TUI.
     Tui {
         /// Path to the database file (.json, .yml, .yaml).
         #[arg(long, default_value = "feeds.json")]
         db: PathBuf,
+        /// Use the monochrome theme even if the terminal supports color.
+        #[arg(long)]
+        no_color: bool,
+        /// Interval between redraw ticks, in milliseconds.
+        #[arg(
+            long,
+            default_value_t = tui::DEFAULT_TICK_MS,
+            value_parser = clap::value_parser!(u64).range(50..=5000)
+        )]
+        tick_ms: u64,
+        /// How long to wait for a keypress before checking the tick, in milliseconds.
+        #[arg(
+            long,
+            default_value_t = tui::DEFAULT_INPUT_POLL_MS,
+            value_parser = clap::value_parser!(u64).range(10..=1000)
+        )]
+        input_poll_ms: u64,
+        /// Capture the mouse so the scroll wheel moves the list under the cursor.
+        #[arg(long)]
+        mouse: bool,
+        /// Create the database's parent directory if it does not exist.
+        #[arg(long)]
+        create_dirs: bool,
+        /// Single linear list with all state spelled out in text, for screen readers.
+        #[arg(long)]
+        simple: bool,
+        /// Days a stored feed title is kept before a refresh replaces it (F always does).
+        #[arg(long, value_name = "DAYS", default_value_t = db::DEFAULT_METADATA_INTERVAL_DAYS)]
+        metadata_interval_days: u32,
+        /// Feeds fetched at the same time when refreshing all of them (R).
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = feed::DEFAULT_JOBS,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        jobs: u32,
+        /// Start with this subscribed feed selected and its newest entry focused.
+        #[arg(long, value_name = "URL")]
+        feed: Option<String>,
+        #[command(flatten)]
+        fetch_args: FetchArgs,
     },
 }
 
+/// How patient fetches are, for every subcommand that fetches feeds.
+#[derive(Args)]
+struct FetchArgs {
+    /// Seconds each request may take before the fetch times out.
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = feed::DEFAULT_TIMEOUT_SECS,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    timeout: u64,
+    /// Retries after a timeout, connection failure or 5xx response, waiting longer each time.
+    #[arg(long, value_name = "N", default_value_t = feed::DEFAULT_RETRIES)]
+    retries: u8,
+}
+
+impl From<FetchArgs> for feed::FetchOptions {
+    fn from(args: FetchArgs) -> Self {
+        Self {
+            timeout_secs: args.timeout,
+            retries: args.retries,
+            ..Self::default()
+        }
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let json = cli.json;
+    let result = run(cli);
+    if json && let Err(err) = &result {
+        output::print_error(err);
+        std::process::exit(1);
+    }
+    result
+}
 
+fn run(cli: Cli) -> Result<()> {
+    let defaults = tui::TuiOptions::default();
     match cli.command.unwrap_or(Command::Tui {
         db: PathBuf::from("feeds.json"),
+        no_color: defaults.no_color,
+        tick_ms: defaults.tick_ms,
+        input_poll_ms: defaults.input_poll_ms,
+        mouse: defaults.mouse,
+        create_dirs: defaults.create_dirs,
+        simple: defaults.simple,
+        metadata_interval_days: defaults.metadata_interval_days,
+        jobs: defaults.jobs,
+        feed: defaults.feed,
+        fetch_args: FetchArgs {
+            timeout: defaults.fetch.timeout_secs,
+            retries: defaults.fetch.retries,
+        },
     }) {
-        Command::Db { path, feed } => {
-            let db = db::load_db(&path)?;
-            render::render_db(db, feed.as_deref());
+        Command::Db {
+            path,
+            feed,
+            feeds_as_list,
+            inactive_since,
+            stats_json,
+            url_only,
+            explain_sort,
+            show_summary,
+            sort_by_date,
+            preserve_order,
+            last_days,
+            last_hours,
+            last_week,
+            last_month,
+            last_year,
+        } => {
+            let access = db::check_db_path(&path, false)?;
+            let mut db = db::load_db(&path)?;
+            // Match --feed like every other feed option, then use the stored spelling.
+            let index = feed.as_deref().map(|url| db.find_feed(url)).transpose()?;
+            let feed = index.map(|index| db.feeds[index].url.clone());
+            // Both flags require --feed, so `index` is set whenever they are.
+            if let Some(index) = index
+                && (sort_by_date || preserve_order)
+            {
+                if access == db::DbAccess::ReadOnly {
+                    anyhow::bail!("database file {} is not writable", path.display());
+                }
+                db.feeds[index].preserve_order = preserve_order;
+                db::save_db(&path, &db)?;
+            }
+            for record in db.feeds.iter_mut().filter(|record| !record.preserve_order) {
+                record.sort_items_by_date();
+            }
+            let now = Utc::now();
+            let cutoff = match (last_days, last_hours) {
+                (Some(days), _) => Some(format::days_ago(days, now)),
+                (_, Some(hours)) => Some(format::hours_ago(hours, now)),
+                _ if last_week => Some(format::days_ago(7, now)),
+                _ if last_month => Some(format::days_ago(30, now)),
+                _ if last_year => Some(format::days_ago(365, now)),
+                _ => None,
+            };
+            if let Some(cutoff) = cutoff {
+                db.retain_items_since(cutoff);
+            }
+            if url_only && cli.json {
+                output::print_json(&render::item_links(&db, feed.as_deref()))?;
+            } else if url_only {
+                render::render_item_links(&db, feed.as_deref());
+            } else if stats_json {
+                let size = std::fs::metadata(&path).ok().map(|meta| meta.len());
+                output::print_json(&output::StatsOutput::new(db.stats(), size))?;
+            } else if let Some(days) = inactive_since {
+                let cutoff = format::days_ago(days, now);
+                let inactive: Vec<&db::FeedRecord> = db
+                    .feeds
+                    .iter()
+                    .filter(|record| feed.as_deref().is_none_or(|url| url == record.url))
+                    .filter(|record| record.last_published().is_none_or(|last| last < cutoff))
+                    .collect();
+                if cli.json {
+                    let feeds: Vec<output::InactiveFeedOutput> =
+                        inactive.into_iter().map(output::InactiveFeedOutput::from).collect();
+                    output::print_json(&feeds)?;
+                } else {
+                    render::render_inactive_feeds(&inactive);
+                }
+            } else if cli.json {
+                let feeds = db
+                    .feeds
+                    .iter()
+                    .filter(|record| feed.as_deref().is_none_or(|url| url == record.url));
+                if feeds_as_list {
+                    let feeds: Vec<output::FeedListOutput> =
+                        feeds.map(output::FeedListOutput::from).collect();
+                    output::print_json(&feeds)?;
+                } else {
+                    let feeds: Vec<output::FeedOutput> =
+                        feeds.map(output::FeedOutput::from).collect();
+                    output::print_json(&feeds)?;
+                }
+            } else {
+                let options = render::ItemOptions {
+                    explain_sort,
+                    show_summary,
+                };
+                render::render_db(db, feed.as_deref(), feeds_as_list, options);
+            }
+        }
+        Command::Fetch {
+            url,
+            follow_pages,
+            since_etag,
+            output_db,
+            append,
+            create_dirs,
+            refresh_metadata,
+            metadata_interval_days,
+            backfill,
+            show_summary,
+            fetch_args,
+        } => {
+            let fetch_options = feed::FetchOptions::from(fetch_args);
+            if let Some(path) = &output_db
+                && db::check_db_path(path, create_dirs)? == db::DbAccess::ReadOnly
+            {
+                anyhow::bail!("database file {} is not writable", path.display());
+            }
+            let runtime = tokio::runtime::Runtime::new()?;
+            let fetched = runtime.block_on(feed::fetch_feed_pages(
+                &url,
+                follow_pages as usize,
+                &feed::CacheValidators {
+                    etag: since_etag,
+                    last_modified: None,
+                },
+                &fetch_options,
+            ));
+            let (meta, items) = match fetched {
+                Err(feed::FetchError::NotModified { .. }) => {
+                    std::process::exit(NOT_MODIFIED_EXIT_CODE)
+                }
+                fetched => fetched?,
+            };
+            if let Some(etag) = &meta.etag {
+                eprintln!("ETag: {etag}");
+            }
+            let warnings = feed::validate_items(&items, Utc::now());
+            if let Some(path) = output_db {
+                let mut db = if append && path.exists() {
+                    db::load_db(&path)?
+                } else {
+                    db::FeedDb::default()
+                };
+                if !cli.json {
+                    for warning in &warnings {
+                        eprintln!("warning: {warning}");
+                    }
+                }
+                let mut count = items.len();
+                let mut skipped = 0;
+                if db.feeds.iter().any(|feed| feed.url == url) {
+                    let interval = chrono::Duration::days(i64::from(metadata_interval_days));
+                    let update_metadata =
+                        refresh_metadata || db.metadata_due(&url, interval, Utc::now());
+                    db.upsert_feed(url.clone(), meta, items, false, update_metadata);
+                } else {
+                    let backfill = backfill.unwrap_or(db.backfill);
+                    skipped = db.add_feed(url.clone(), meta, items, backfill).1;
+                    count -= skipped;
+                }
+                db::save_db(&path, &db)?;
+                if cli.json {
+                    let saved = output::SavedOutput {
+                        url: &url,
+                        path: &path,
+                        saved: count,
+                        skipped_by_backfill: skipped,
+                        warnings,
+                    };
+                    output::print_json(&saved)?;
+                } else {
+                    let mut message =
+                        format!("Saved {count} items from {url} to {}", path.display());
+                    if skipped > 0 {
+                        message.push_str(&format!(" ({skipped} older items skipped by backfill)"));
+                    }
+                    println!("{message}");
+                }
+            } else if cli.json {
+                let feed = output::FeedOutput::new(meta.title.as_deref(), &url, warnings, &items);
+                output::print_json(&feed)?;
+            } else {
+                for warning in warnings {
+                    eprintln!("warning: {warning}");
+                }
+                let options = render::ItemOptions {
+                    explain_sort: false,
+                    show_summary,
+                };
+                render::render_items(meta.title.as_deref().unwrap_or(&url), &items, options);
+            }
+        }
+        Command::Import {
+            path,
+            db: db_path,
+            create_dirs,
+            fetch,
+            backfill,
+            jobs,
+            fetch_args,
+        } => {
+            if db::check_db_path(&db_path, create_dirs)? == db::DbAccess::ReadOnly {
+                anyhow::bail!("database file {} is not writable", db_path.display());
+            }
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let mut db = if db_path.exists() {
+                db::load_db(&db_path)?
+            } else {
+                db::FeedDb::default()
+            };
+            let backfill = backfill.unwrap_or(db.backfill);
+            let summary = opml::import_opml(&mut db, &content, backfill)?;
+            let mut failed = 0;
+            let mut skipped = 0;
+            if fetch {
+                let runtime = tokio::runtime::Runtime::new()?;
+                let fetch_options = feed::FetchOptions::from(fetch_args);
+                let feeds = summary
+                    .added
+                    .iter()
+                    .map(|url| (url.clone(), feed::CacheValidators::default()))
+                    .collect();
+                let results =
+                    runtime.block_on(feed::fetch_all(feeds, jobs as usize, fetch_options));
+                for (url, result) in results {
+                    match result {
+                        Ok((meta, items)) => {
+                            let Some(feed) = db.feeds.iter().find(|feed| feed.url == url) else {
+                                continue;
+                            };
+                            // Keep the name the OPML file gave the feed, if it gave one.
+                            let untitled = feed.title.is_none();
+                            // This is the feed's first fetch, so its backfill policy applies.
+                            let fetched = items.len();
+                            let index = db.upsert_feed(url, meta, items, false, untitled);
+                            skipped += fetched - db.feeds[index].items.len();
+                        }
+                        Err(err) => {
+                            let http_status = err.http_status();
+                            let err = format!("{:#}", anyhow::Error::from(err));
+                            eprintln!("warning: {err}");
+                            db.record_fetch_error(&url, err, http_status);
+                            failed += 1;
+                        }
+                    }
+                }
+            }
+            db::save_db(&db_path, &db)?;
+            if cli.json {
+                output::print_json(&output::ImportOutput::new(&summary, failed))?;
+            } else {
+                let mut message = format!(
+                    "Imported {} feeds from {} to {} ({} skipped as already subscribed",
+                    summary.added.len(),
+                    path.display(),
+                    db_path.display(),
+                    summary.already_present
+                );
+                if fetch {
+                    message.push_str(&format!(", {failed} failed to fetch"));
+                }
+                if skipped > 0 {
+                    message.push_str(&format!(", {skipped} older entries skipped by backfill"));
+                }
+                println!("{message})");
+            }
+        }
+        Command::Export {
+            path,
+            format,
+            db: db_path,
+        } => {
+            db::check_db_path(&db_path, false)?;
+            let db = db::load_db(&db_path)?;
+            let count = export::write_export(&db, &path, format)?;
+            if cli.json {
+                output::print_json(&output::ExportOutput {
+                    exported: count,
+                    path: &path,
+                })?;
+            } else {
+                println!("Exported {count} feeds to {}", path.display());
+            }
         }
-        Command::Fetch { url } => {
-            let (title, items) = feed::fetch_feed_items(&url)?;
-            render::render_items(title.as_deref().unwrap_or(&url), &items);
+        Command::Api { db, fetch_args } => {
+            let (input, output) = (std::io::stdin().lock(), std::io::stdout().lock());
+            api::run_api(&db, fetch_args.into(), input, output)?;
         }
-        Command::Tui { db } => {
-            tui::run_tui(db)?;
+        Command::Tui {
+            db,
+            no_color,
+            tick_ms,
+            input_poll_ms,
+            mouse,
+            create_dirs,
+            simple,
+            metadata_interval_days,
+            jobs,
+            feed,
+            fetch_args,
+        } => {
+            let options = tui::TuiOptions {
+                no_color,
+                tick_ms,
+                input_poll_ms,
+                mouse,
+                create_dirs,
+                simple,
+                metadata_interval_days,
+                jobs,
+                feed,
+                fetch: fetch_args.into(),
+            };
+            tui::run_tui(db, options)?;
         }
     }
 
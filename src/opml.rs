@@ -0,0 +1,198 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use thiserror::Error;
+
+use crate::db::{Backfill, FeedDb};
+use crate::feed::FeedMeta;
+
+#[derive(Debug, Error)]
+pub enum OpmlError {
+    #[error("invalid OPML at byte {position}")]
+    Parse {
+        position: usize,
+        #[source]
+        source: quick_xml::Error,
+    },
+}
+
+/// Counts from importing an OPML subscription list.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// URLs of the feeds that were subscribed, in file order.
+    pub added: Vec<String>,
+    /// Outlines whose feed was already subscribed, or listed twice.
+    pub already_present: usize,
+}
+
+/// Returns the `(text, xmlUrl)` of every `<outline>` that names a feed, at any
+/// depth, in document order. Category outlines without an `xmlUrl` are skipped.
+pub fn parse_opml(content: &str) -> Result<Vec<(Option<String>, String)>, OpmlError> {
+    let mut reader = Reader::from_str(content);
+    let mut outlines = Vec::new();
+    loop {
+        let event = reader.read_event().map_err(|source| OpmlError::Parse {
+            position: reader.buffer_position(),
+            source,
+        })?;
+        match event {
+            Event::Start(element) | Event::Empty(element)
+                if element.local_name().as_ref() == b"outline" =>
+            {
+                let outline = read_outline(&element, &reader).map_err(|source| OpmlError::Parse {
+                    position: reader.buffer_position(),
+                    source,
+                })?;
+                outlines.extend(outline);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(outlines)
+}
+
+fn read_outline(
+    element: &BytesStart,
+    reader: &Reader<&[u8]>,
+) -> Result<Option<(Option<String>, String)>, quick_xml::Error> {
+    let mut text = None;
+    let mut title = None;
+    let mut url = None;
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        let key = attribute.key.local_name();
+        let value = attribute.decode_and_unescape_value(reader)?.trim().to_string();
+        if value.is_empty() {
+            continue;
+        }
+        // Exporters disagree on the case of `xmlUrl`.
+        if key.as_ref().eq_ignore_ascii_case(b"xmlurl") {
+            url = Some(value);
+        } else if key.as_ref() == b"text" {
+            text = Some(value);
+        } else if key.as_ref() == b"title" {
+            title = Some(value);
+        }
+    }
+    Ok(url.map(|url| (text.or(title), url)))
+}
+
+/// Subscribes `db` to every feed in an OPML document without fetching them;
+/// the feeds start out empty until they are refreshed, and `backfill` is
+/// recorded for their first fetch.
+pub fn import_opml(
+    db: &mut FeedDb,
+    content: &str,
+    backfill: Backfill,
+) -> Result<ImportSummary, OpmlError> {
+    let mut summary = ImportSummary::default();
+    for (title, url) in parse_opml(content)? {
+        if db.find_duplicate(&url).is_some() {
+            summary.already_present += 1;
+            continue;
+        }
+        let meta = FeedMeta {
+            title,
+            ..FeedMeta::default()
+        };
+        db.add_feed(url.clone(), meta, Vec::new(), backfill);
+        summary.added.push(url);
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed from a Feedly export: folders nest the feeds, which carry both
+    /// `text` and `title`.
+    const FEEDLY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+
+<opml version="1.0">
+    <head>
+        <title>Reader subscriptions in feedly Cloud</title>
+    </head>
+    <body>
+        <outline text="Tech" title="Tech">
+            <outline type="rss" text="Ars Technica" title="Ars Technica" xmlUrl="https://feeds.arstechnica.com/arstechnica/index" htmlUrl="https://arstechnica.com"/>
+            <outline type="rss" text="The Verge -  All Posts" title="The Verge -  All Posts" xmlUrl="https://www.theverge.com/rss/index.xml" htmlUrl="https://www.theverge.com/"/>
+        </outline>
+        <outline text="Rust" title="Rust">
+            <outline text="Releases" title="Releases">
+                <outline type="rss" text="Rust Blog" title="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml" htmlUrl="https://blog.rust-lang.org/"/>
+            </outline>
+        </outline>
+    </body>
+</opml>
+"#;
+
+    /// Trimmed from `newsboat -e`: a flat list with `title` only.
+    const NEWSBOAT: &str = r#"<?xml version="1.0"?>
+<opml version="1.0">
+  <head>
+    <title>newsboat - Exported Feeds</title>
+  </head>
+  <body>
+    <outline type="rss" xmlUrl="https://lwn.net/headlines/rss" htmlUrl="https://lwn.net" title="LWN.net"/>
+    <outline type="rss" xmlUrl="https://example.com/search?q=rust&amp;format=rss" htmlUrl="https://example.com/" title="Search: rust &amp; friends"/>
+    <outline type="rss" xmlUrl="https://blog.rust-lang.org/feed.xml" htmlUrl="" title=""/>
+  </body>
+</opml>
+"#;
+
+    fn outline(title: Option<&str>, url: &str) -> (Option<String>, String) {
+        (title.map(str::to_string), url.to_string())
+    }
+
+    #[test]
+    fn parses_nested_feedly_folders() {
+        assert_eq!(
+            parse_opml(FEEDLY).unwrap(),
+            [
+                outline(Some("Ars Technica"), "https://feeds.arstechnica.com/arstechnica/index"),
+                outline(Some("The Verge -  All Posts"), "https://www.theverge.com/rss/index.xml"),
+                outline(Some("Rust Blog"), "https://blog.rust-lang.org/feed.xml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_newsboat_titles_and_entities() {
+        assert_eq!(
+            parse_opml(NEWSBOAT).unwrap(),
+            [
+                outline(Some("LWN.net"), "https://lwn.net/headlines/rss"),
+                outline(
+                    Some("Search: rust & friends"),
+                    "https://example.com/search?q=rust&format=rss"
+                ),
+                outline(None, "https://blog.rust-lang.org/feed.xml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_skips_feeds_already_subscribed() {
+        let mut db = FeedDb::default();
+        import_opml(&mut db, FEEDLY, Backfill::All).unwrap();
+        let summary = import_opml(&mut db, NEWSBOAT, Backfill::None).unwrap();
+
+        assert_eq!(
+            summary.added,
+            ["https://lwn.net/headlines/rss", "https://example.com/search?q=rust&format=rss"]
+        );
+        assert_eq!(summary.already_present, 1);
+        assert_eq!(db.feeds.len(), 5);
+        assert!(db.feeds.iter().all(|feed| feed.items.is_empty()));
+        assert_eq!(db.feeds[0].title.as_deref(), Some("Ars Technica"));
+        assert_eq!(db.feeds[4].backfill, Backfill::None);
+    }
+
+    #[test]
+    fn malformed_opml_reports_a_position() {
+        let err = parse_opml("<opml><body><outline xmlUrl=\"x\"></body></opml>").unwrap_err();
+        let OpmlError::Parse { position, .. } = err;
+        assert!(position > 0);
+    }
+}
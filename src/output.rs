@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::db::{DbStats, FeedItem, FeedRecord};
+use crate::opml::ImportSummary;
+
+/// JSON shape of a feed for `--json` output. Kept separate from the db types
+/// so the on-disk format can change without breaking scripts.
+#[derive(Serialize)]
+pub struct FeedOutput<'a> {
+    pub title: Option<&'a str>,
+    pub url: &'a str,
+    pub warnings: Vec<String>,
+    pub items: Vec<ItemOutput<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct ItemOutput<'a> {
+    /// What `api` `mark_read` requests name the entry by.
+    pub key: String,
+    pub title: &'a str,
+    pub link: Option<&'a str>,
+    pub published: Option<&'a str>,
+    pub author: Option<&'a str>,
+    pub summary: Option<&'a str>,
+    pub read: bool,
+}
+
+/// A feed listed by `db --feeds-as-list`, without its items.
+#[derive(Serialize)]
+pub struct FeedListOutput<'a> {
+    pub title: Option<&'a str>,
+    pub url: &'a str,
+}
+
+/// A feed listed by `db --inactive-since`.
+#[derive(Serialize)]
+pub struct InactiveFeedOutput<'a> {
+    pub title: Option<&'a str>,
+    pub url: &'a str,
+    pub last_published: Option<String>,
+    pub item_count: usize,
+}
+
+/// JSON shape of `db --stats-json`.
+#[derive(Serialize)]
+pub struct StatsOutput {
+    pub total_feeds: usize,
+    pub total_items: usize,
+    pub unread_items: usize,
+    /// Always null: entries cannot be starred yet.
+    pub starred_items: Option<usize>,
+    pub feeds_with_errors: usize,
+    pub oldest_item_date: Option<String>,
+    pub newest_item_date: Option<String>,
+    pub database_size_bytes: Option<u64>,
+}
+
+impl StatsOutput {
+    pub fn new(stats: DbStats, database_size_bytes: Option<u64>) -> Self {
+        Self {
+            total_feeds: stats.total_feeds,
+            total_items: stats.total_items,
+            unread_items: stats.unread_items,
+            starred_items: None,
+            feeds_with_errors: stats.feeds_with_errors,
+            oldest_item_date: stats.oldest_item_date.map(|date| date.to_rfc3339()),
+            newest_item_date: stats.newest_item_date.map(|date| date.to_rfc3339()),
+            database_size_bytes,
+        }
+    }
+}
+
+/// JSON shape of `fetch --output-db` with `--json`.
+#[derive(Serialize)]
+pub struct SavedOutput<'a> {
+    pub url: &'a str,
+    pub path: &'a Path,
+    /// Entries stored for the feed by this fetch.
+    pub saved: usize,
+    pub skipped_by_backfill: usize,
+    pub warnings: Vec<String>,
+}
+
+/// JSON shape of `import --json`.
+#[derive(Serialize)]
+pub struct ImportOutput {
+    pub added: usize,
+    pub already_present: usize,
+    /// Imported feeds whose first fetch failed, with `--fetch`.
+    pub failed: usize,
+}
+
+impl ImportOutput {
+    pub fn new(summary: &ImportSummary, failed: usize) -> Self {
+        Self {
+            added: summary.added.len(),
+            already_present: summary.already_present,
+            failed,
+        }
+    }
+}
+
+/// JSON shape of `export --json`.
+#[derive(Serialize)]
+pub struct ExportOutput<'a> {
+    pub exported: usize,
+    pub path: &'a Path,
+}
+
+#[derive(Serialize)]
+pub struct ErrorOutput {
+    pub error: String,
+}
+
+impl<'a> FeedOutput<'a> {
+    pub fn new(
+        title: Option<&'a str>,
+        url: &'a str,
+        warnings: Vec<String>,
+        items: &'a [FeedItem],
+    ) -> Self {
+        Self {
+            title,
+            url,
+            warnings,
+            items: items.iter().map(ItemOutput::from).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a FeedRecord> for FeedOutput<'a> {
+    fn from(feed: &'a FeedRecord) -> Self {
+        Self::new(feed.title.as_deref(), &feed.url, feed.warnings.clone(), &feed.items)
+    }
+}
+
+impl<'a> From<&'a FeedRecord> for FeedListOutput<'a> {
+    fn from(feed: &'a FeedRecord) -> Self {
+        Self {
+            title: feed.title.as_deref(),
+            url: &feed.url,
+        }
+    }
+}
+
+impl<'a> From<&'a FeedRecord> for InactiveFeedOutput<'a> {
+    fn from(feed: &'a FeedRecord) -> Self {
+        Self {
+            title: feed.title.as_deref(),
+            url: &feed.url,
+            last_published: feed.last_published().map(|date| date.to_rfc3339()),
+            item_count: feed.items.len(),
+        }
+    }
+}
+
+impl<'a> From<&'a FeedItem> for ItemOutput<'a> {
+    fn from(item: &'a FeedItem) -> Self {
+        Self {
+            key: item.key(),
+            title: &item.title,
+            link: item.link.as_deref(),
+            published: item.published.as_deref(),
+            author: item.author.as_deref(),
+            summary: item.summary.as_deref(),
+            read: item.read,
+        }
+    }
+}
+
+pub fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+pub fn print_error(err: &anyhow::Error) {
+    let output = ErrorOutput {
+        error: format!("{err:#}"),
+    };
+    match serde_json::to_string(&output) {
+        Ok(json) => eprintln!("{json}"),
+        Err(_) => eprintln!("{err:#}"),
+    }
+}
@@ -1,33 +1,91 @@
-use crate::db::{FeedDb, FeedItem};
+use crate::db::{FeedDb, FeedItem, FeedRecord};
+use crate::format::explain_published;
 
-pub fn render_db(db: FeedDb, filter_url: Option<&str>) {
-    for feed in db.feeds {
-        if filter_url.is_some_and(|url| url != feed.url) {
-            continue;
+/// What `render_items` prints alongside each entry's line.
+#[derive(Clone, Copy, Default)]
+pub struct ItemOptions {
+    /// Append the date each entry sorts by.
+    pub explain_sort: bool,
+    /// Print the entry's summary, indented, under its line.
+    pub show_summary: bool,
+}
+
+pub fn render_db(db: FeedDb, filter_url: Option<&str>, feeds_as_list: bool, options: ItemOptions) {
+    let feeds = db
+        .feeds
+        .into_iter()
+        .filter(|feed| filter_url.is_none_or(|url| url == feed.url));
+    if feeds_as_list {
+        for feed in feeds {
+            println!("{} | {}", feed.url, feed.title.as_deref().unwrap_or("Untitled"));
         }
+        return;
+    }
+    for feed in feeds {
         let label = format!(
             "{} ({})",
             feed.title.as_deref().unwrap_or("Untitled"),
             feed.url
         );
-        render_items(&label, &feed.items);
+        render_items(&label, &feed.items, options);
         println!();
     }
 }
 
-pub fn render_items(label: &str, items: &[FeedItem]) {
+/// Every entry link in `db`, or in the feed `filter_url`, in stored order.
+pub fn item_links<'a>(db: &'a FeedDb, filter_url: Option<&str>) -> Vec<&'a str> {
+    db.feeds
+        .iter()
+        .filter(|feed| filter_url.is_none_or(|url| url == feed.url))
+        .flat_map(|feed| &feed.items)
+        .filter_map(|item| item.link.as_deref())
+        .collect()
+}
+
+pub fn render_item_links(db: &FeedDb, filter_url: Option<&str>) {
+    for link in item_links(db, filter_url) {
+        println!("{link}");
+    }
+}
+
+pub fn render_inactive_feeds(feeds: &[&FeedRecord]) {
+    for feed in feeds {
+        let last = feed
+            .last_published()
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_else(|| "never".to_string());
+        println!(
+            "{} | {} | last item {} | {} items",
+            feed.title.as_deref().unwrap_or("Untitled"),
+            feed.url,
+            last,
+            feed.items.len()
+        );
+    }
+}
+
+pub fn render_items(label: &str, items: &[FeedItem], options: ItemOptions) {
     println!("Feed: {}", label);
     for item in items {
-        let link = item.link.as_deref().unwrap_or_default();
-        let published = item.published.as_deref().unwrap_or_default();
-        if published.is_empty() && link.is_empty() {
-            println!("- {}", item.title);
-        } else if published.is_empty() {
-            println!("- {} | {}", item.title, link);
-        } else if link.is_empty() {
-            println!("- {} | {}", item.title, published);
+        let fields = [item.published.as_deref(), item.link.as_deref(), item.author.as_deref()];
+        let mut line = format!("- {}", item.title);
+        for field in fields.into_iter().flatten().filter(|field| !field.is_empty()) {
+            line.push_str(" | ");
+            line.push_str(field);
+        }
+        if options.explain_sort {
+            println!("{line} | sort key: {}", explain_published(item));
         } else {
-            println!("- {} | {} | {}", item.title, published, link);
+            println!("{line}");
+        }
+        if options.show_summary && let Some(summary) = &item.summary {
+            for summary_line in summary.lines() {
+                if summary_line.is_empty() {
+                    println!();
+                } else {
+                    println!("    {summary_line}");
+                }
+            }
         }
     }
 }
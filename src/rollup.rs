@@ -0,0 +1,97 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+
+/// Block characters for terminals that can draw them.
+pub const SPARK_LEVELS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Plain ASCII steps for the mono theme and terminals without block glyphs.
+pub const ASCII_SPARK_LEVELS: &[char] = &['_', '.', '-', '=', '#'];
+
+/// Counts dates per ISO week (Monday start, UTC) for the `weeks` weeks ending
+/// with the week containing `now`. The result is ordered oldest first; dates
+/// outside the window are ignored.
+pub fn weekly_counts<I>(dates: I, now: DateTime<Utc>, weeks: usize) -> Vec<usize>
+where
+    I: IntoIterator<Item = DateTime<Utc>>,
+{
+    let mut counts = vec![0; weeks];
+    let current = week_start(now.date_naive());
+    for date in dates {
+        let age = (current - week_start(date.date_naive())).num_weeks();
+        if (0..weeks as i64).contains(&age) {
+            counts[weeks - 1 - age as usize] += 1;
+        }
+    }
+    counts
+}
+
+/// Draws one character per count, scaled to the largest count, using `levels`
+/// from lowest to highest; empty weeks are blank.
+pub fn sparkline(counts: &[usize], levels: &[char]) -> String {
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|&count| {
+            if max == 0 || count == 0 {
+                ' '
+            } else {
+                levels[(count * levels.len()).div_ceil(max) - 1]
+            }
+        })
+        .collect()
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2024-01-07 is a Sunday, 2024-01-08 a Monday.
+        let now = at("2024-01-10T12:00:00Z");
+        let dates = [
+            at("2024-01-07T23:59:59Z"),
+            at("2024-01-08T00:00:00Z"),
+            at("2024-01-01T00:00:00Z"),
+            at("2023-12-31T23:59:59Z"),
+        ];
+        assert_eq!(weekly_counts(dates, now, 3), vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn weeks_are_counted_in_utc() {
+        // Monday 01:00 at +02:00 is still Sunday in UTC, and Sunday 22:00 at
+        // -03:00 is already Monday.
+        let now = at("2024-01-08T00:00:00Z");
+        let dates = [at("2024-01-08T01:00:00+02:00"), at("2024-01-07T22:00:00-03:00")];
+        assert_eq!(weekly_counts(dates, now, 2), vec![1, 1]);
+    }
+
+    #[test]
+    fn weeks_span_the_new_year() {
+        // ISO week 1 of 2025 starts on Monday 2024-12-30.
+        let now = at("2025-01-01T00:00:00Z");
+        let dates = [at("2024-12-30T00:00:00Z"), at("2024-12-29T23:00:00Z")];
+        assert_eq!(weekly_counts(dates, now, 2), vec![1, 1]);
+    }
+
+    #[test]
+    fn dates_outside_the_window_are_ignored() {
+        let now = at("2024-01-10T00:00:00Z");
+        let dates = [at("2024-01-15T00:00:00Z"), at("2023-12-31T00:00:00Z")];
+        assert_eq!(weekly_counts(dates, now, 2), vec![0, 0]);
+    }
+
+    #[test]
+    fn sparkline_scales_to_the_busiest_week() {
+        assert_eq!(sparkline(&[0, 1, 4, 8], SPARK_LEVELS), " ▁▄█");
+        assert_eq!(sparkline(&[0, 1, 3, 5], ASCII_SPARK_LEVELS), " _-#");
+        assert_eq!(sparkline(&[0, 0], ASCII_SPARK_LEVELS), "  ");
+    }
+}
@@ -0,0 +1,117 @@
+//! Helpers shared by the unit tests.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::db::FeedItem;
+
+/// Creates an empty directory under the system temp dir that no other test
+/// uses.
+pub fn temp_dir(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!(
+        "rss-cli-test-{}-{count}-{name}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// An undated, unread entry with a title and link and nothing else.
+pub fn item(title: &str, link: &str) -> FeedItem {
+    FeedItem {
+        id: None,
+        title: title.to_string(),
+        link: Some(link.to_string()),
+        published: None,
+        published_raw: None,
+        published_error: None,
+        title_synthesized: false,
+        read: false,
+        author: None,
+        summary: None,
+        missing_since: None,
+    }
+}
+
+/// Serves HTTP on a local port until the test process exits. `respond` gets
+/// each raw request head and returns the whole response; the result is the
+/// base URL.
+pub fn serve<F>(respond: F) -> String
+where
+    F: Fn(&str) -> String + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut head = Vec::new();
+            let mut buf = [0; 1024];
+            while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => head.extend_from_slice(&buf[..read]),
+                }
+            }
+            let response = respond(&String::from_utf8_lossy(&head));
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    format!("http://{addr}")
+}
+
+/// A complete response with `status` and any extra header lines.
+pub fn response(status: &str, headers: &[&str], body: &str) -> String {
+    let mut response = format!("HTTP/1.1 {status}\r\nContent-Length: {}\r\n", body.len());
+    for header in headers {
+        response.push_str(header);
+        response.push_str("\r\n");
+    }
+    response.push_str("Connection: close\r\n\r\n");
+    response.push_str(body);
+    response
+}
+
+/// An RSS document with one dated entry per title, linked under example.com.
+pub fn rss(titles: &[&str]) -> String {
+    let items: String = titles
+        .iter()
+        .map(|title| {
+            format!(
+                "<item><title>{title}</title><link>https://example.com/{title}</link>\
+                 <guid>{title}</guid><pubDate>Mon, 01 Jan 2024 00:00:00 GMT</pubDate></item>"
+            )
+        })
+        .collect();
+    format!("<rss version=\"2.0\"><channel><title>Example</title>{items}</channel></rss>")
+}
+
+/// Serves a feed with validators, answering 304 to a request that sends
+/// `header` with the value the feed was served with.
+pub fn conditional_server(header: &'static str, value: &'static str) -> String {
+    serve(move |request| {
+        let matched = request.lines().any(|line| {
+            line.split_once(':').is_some_and(|(name, sent)| {
+                name.eq_ignore_ascii_case(header) && sent.trim() == value
+            })
+        });
+        let validators = [
+            "ETag: \"v1\"",
+            "Last-Modified: Mon, 01 Jan 2024 00:00:00 GMT",
+        ];
+        if matched {
+            response("304 Not Modified", &validators, "")
+        } else {
+            response("200 OK", &validators, &rss(&["one"]))
+        }
+    })
+}
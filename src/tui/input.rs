@@ -1,15 +1,76 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Instant;
+
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use chrono::{DateTime, Duration, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+
+use crate::db::{FeedItem, FeedRecord};
+use crate::export::{write_export, ExportFormat};
+use crate::feed::{
+    fetch_all, fetch_feed_items_opts, fetch_feed_pages, CacheValidators, FeedMeta, FetchError,
+    FetchedFeed,
+};
 
-use crate::db::save_db;
-use crate::feed::fetch_feed_items;
+use super::simple_ui;
+use super::state::{AddPreview, App, DisplayItem, Focus, Mode, PendingAdd, RefreshDone, PAGE_JUMP};
 
-use super::state::{App, Focus, Mode, PAGE_JUMP};
+/// Back-off used when a server rate limits us without saying for how long.
+const DEFAULT_RATE_LIMIT_SECS: u64 = 15 * 60;
 
 pub(super) fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
     match app.mode {
+        Mode::Loading => Ok(matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)),
+        Mode::LoadFailed => match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Ok(true),
+            KeyCode::Char('r') => {
+                app.status.clear();
+                app.start_load();
+                Ok(false)
+            }
+            _ => Ok(false),
+        },
         Mode::AddUrl => handle_add_url(app, key),
+        Mode::Normal if app.simple => handle_simple(app, key),
         Mode::Normal => handle_normal(app, key),
+        Mode::ConfirmDuplicate => handle_confirm_duplicate(app, key),
+        Mode::ConfirmQuit => handle_confirm_quit(app, key),
+        Mode::AddPreview => handle_add_preview(app, key),
+        Mode::FeedSettings => handle_feed_settings(app, key),
+        Mode::SetPriority => handle_set_priority(app, key),
+        Mode::ImportPath => handle_import_path(app, key),
+        Mode::ExportPath => handle_export_path(app, key),
+        Mode::Reading => handle_reading(app, key),
+        Mode::FeedInfo => {
+            app.mode = Mode::Normal;
+            Ok(false)
+        }
+        Mode::ShowStatus => {
+            match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    app.status_scroll = app.status_scroll.saturating_add(1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    app.status_scroll = app.status_scroll.saturating_sub(1);
+                }
+                _ => app.mode = Mode::Normal,
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Scrolls the pane under the mouse cursor; other mouse events are ignored.
+pub(super) fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    if !matches!(app.mode, Mode::Normal) {
+        return;
+    }
+    match mouse.kind {
+        MouseEventKind::ScrollDown => app.scroll_panel_at(mouse.column, 1),
+        MouseEventKind::ScrollUp => app.scroll_panel_at(mouse.column, -1),
+        _ => {}
     }
 }
 
@@ -28,13 +89,13 @@ fn handle_add_url(app: &mut App, key: KeyEvent) -> Result<bool> {
                 app.status = "URL cannot be empty.".to_string();
                 return Ok(false);
             }
-            match fetch_feed_items(&url) {
-                Ok((title, items)) => {
-                    app.upsert_feed(url.clone(), title, items)?;
-                    app.status = format!("Added {url}");
-                }
-                Err(err) => app.status = format!("Error: {err}"),
+            if let Some(index) = app.find_duplicate(&url) {
+                let existing_url = app.db.feeds[index].url.clone();
+                app.pending_add = Some(PendingAdd { url, existing_url });
+                app.mode = Mode::ConfirmDuplicate;
+                return Ok(false);
             }
+            add_feed(app, url)?;
         }
         KeyCode::Backspace => {
             app.input.pop();
@@ -50,26 +111,260 @@ fn handle_add_url(app: &mut App, key: KeyEvent) -> Result<bool> {
     Ok(false)
 }
 
+/// Resolves an add whose URL matches an existing feed: refresh the existing
+/// feed, keep both under a different URL, or cancel.
+fn handle_confirm_duplicate(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Char('r') => {
+            let Some(pending) = app.pending_add.take() else {
+                app.mode = Mode::Normal;
+                return Ok(false);
+            };
+            app.mode = Mode::Normal;
+            let existing = app.db.feeds.iter().position(|feed| feed.url == pending.existing_url);
+            if let Some(index) = existing {
+                app.select_feed(index + 1);
+                refresh_selected(app, false)?;
+            }
+        }
+        KeyCode::Char('k') => {
+            let Some(pending) = app.pending_add.take() else {
+                app.mode = Mode::Normal;
+                return Ok(false);
+            };
+            if pending.url != pending.existing_url {
+                app.mode = Mode::Normal;
+                add_feed(app, pending.url)?;
+            } else {
+                // The exact URL is already stored; let the user change it.
+                app.mode = Mode::AddUrl;
+                app.input = pending.url;
+                app.status = "Edit the URL to keep both feeds.".to_string();
+            }
+        }
+        KeyCode::Esc => {
+            app.pending_add = None;
+            app.mode = Mode::Normal;
+            app.status = "Add cancelled.".to_string();
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Asked when quitting with a background fetch running: quit now and drop its
+/// result, wait for it to be stored and then quit, or stop it and quit.
+fn handle_confirm_quit(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Char('q') => return Ok(true),
+        KeyCode::Char('w') => {
+            app.mode = Mode::Normal;
+            app.quit_when_refreshed = true;
+        }
+        KeyCode::Char('c') => {
+            if let Some(task) = app.refresh_task.take() {
+                task.abort();
+            }
+            return Ok(true);
+        }
+        KeyCode::Esc => app.mode = Mode::Normal,
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Starts fetching a feed to be added in the background; [`poll_refresh`]
+/// shows it for confirmation when it arrives, and nothing is saved until the
+/// preview is accepted.
+fn add_feed(app: &mut App, url: String) -> Result<()> {
+    if refresh_running(app) {
+        // Keep the URL so it can be sent again once the refresh is done.
+        app.input = url;
+        app.mode = Mode::AddUrl;
+        return Ok(());
+    }
+    let label = format!("Fetching {}", host_label(&url));
+    let options = app.fetch_options;
+    start_fetch(app, label, async move {
+        let result = fetch_feed_items_opts(&url, &options).await;
+        RefreshDone::Add { url, result }
+    });
+    Ok(())
+}
+
+/// Opens the preview of a fetched feed, unless another prompt was opened in
+/// the meantime.
+fn finish_add(
+    app: &mut App,
+    url: String,
+    result: Result<(FeedMeta, Vec<FeedItem>), FetchError>,
+) {
+    match result {
+        Ok(_) if !matches!(app.mode, Mode::Normal) => {
+            app.status = format!("Fetched {url}; add it again to preview it.");
+        }
+        Ok((meta, items)) => {
+            app.add_preview = Some(AddPreview { url, meta, items });
+            app.mode = Mode::AddPreview;
+        }
+        Err(err) => app.status = format!("Error: {:#}", anyhow::Error::from(err)),
+    }
+}
+
+fn handle_add_preview(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Enter => {
+            app.mode = Mode::Normal;
+            if let Some(AddPreview { url, meta, items }) = app.add_preview.take() {
+                let skipped = app.add_feed(url.clone(), meta, items)?;
+                let mut status = format!("Added {url}");
+                if skipped > 0 {
+                    status.push_str(&format!(" ({skipped} older entries skipped by backfill)"));
+                }
+                app.status = with_warnings(status, app.selected_feed());
+            }
+        }
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+            if let Some(preview) = app.add_preview.take() {
+                app.status = format!("Discarded {}", preview.url);
+            }
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// The reduced key map of the simple view: linear movement, Enter to read
+/// out the selection in full, and the plain feed actions.
+fn handle_simple(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => simple_ui::move_row(app, -1),
+        KeyCode::Down | KeyCode::Char('j') => simple_ui::move_row(app, 1),
+        KeyCode::PageUp => simple_ui::move_row(app, -PAGE_JUMP),
+        KeyCode::PageDown => simple_ui::move_row(app, PAGE_JUMP),
+        KeyCode::Enter => app.status = simple_ui::describe_selection(app, true),
+        KeyCode::Esc
+        | KeyCode::Char(
+            'q' | 'a' | 'e' | 'i' | 'm' | 'u' | 'U' | 'r' | 'R' | 'o' | 'F' | 'd' | 'E' | 'f' | 'z',
+        ) => {
+            return handle_normal(app, key);
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
 fn handle_normal(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    if !(ctrl && matches!(key.code, KeyCode::Up | KeyCode::Down)) {
+        app.dragging_feed = None;
+    }
     match key.code {
+        KeyCode::Esc if !app.selected_items.is_empty() => clear_item_selection(app),
+        KeyCode::Char('d') if ctrl => clear_item_selection(app),
+        KeyCode::Char('p') if ctrl => {
+            if let Some(feed) = app.selected_feed() {
+                app.input = feed.priority.to_string();
+                app.mode = Mode::SetPriority;
+            } else {
+                app.status = "Select a feed to set its priority.".to_string();
+            }
+        }
+        KeyCode::Char('a') if ctrl => {
+            if app.focus == Focus::Items {
+                let count = app.select_all_items();
+                app.status = format!("[All {count} selected]");
+            } else {
+                app.status = "Focus the entries to select them.".to_string();
+            }
+        }
+        KeyCode::Char('q') | KeyCode::Esc if app.refreshing.is_some() => {
+            app.mode = Mode::ConfirmQuit;
+        }
         KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+        KeyCode::Char('z') if ctrl => {
+            if cfg!(unix) {
+                app.suspend_requested = true;
+            } else {
+                app.status = "Suspend not supported on this platform.".to_string();
+            }
+        }
         KeyCode::Char('a') => {
             app.mode = Mode::AddUrl;
             app.input.clear();
             app.status = "Enter feed URL.".to_string();
         }
-        KeyCode::Char('r') => {
-            if let Some(feed) = app.selected_feed() {
-                let url = feed.url.clone();
-                match fetch_feed_items(&url) {
-                    Ok((title, items)) => {
-                        app.upsert_feed(url.clone(), title, items)?;
-                        app.status = format!("Refreshed {url}");
-                    }
-                    Err(err) => app.status = format!("Error: {err}"),
-                }
+        KeyCode::Char('e') => {
+            app.mode = Mode::ExportPath;
+            app.input.clear();
+            app.status = "Enter the path to write the OPML file to.".to_string();
+        }
+        KeyCode::Char('i') => {
+            app.mode = Mode::ImportPath;
+            app.input.clear();
+            app.status = "Enter the path of an OPML file.".to_string();
+        }
+        KeyCode::Char('r') => refresh_selected(app, false)?,
+        KeyCode::Char('R') => refresh_all(app)?,
+        KeyCode::Char('o') => open_selected_link(app)?,
+        KeyCode::Enter if app.focus == Focus::Items => open_reading_view(app)?,
+        KeyCode::Char(' ') if app.focus == Focus::Items => {
+            if let Some(item) = app.selected_item()
+                && !item.read
+                && app.set_read(&item.source, true)?
+            {
+                app.status = "Marked as read.".to_string();
+            }
+        }
+        KeyCode::Char('u') => {
+            if app.focus != Focus::Items {
+                app.status = "Focus the entries to mark one.".to_string();
+            } else {
+                app.status = match app.toggle_selected_read()? {
+                    Some(true) => "Marked as read.".to_string(),
+                    Some(false) => "Marked as unread.".to_string(),
+                    None => "Select an entry to mark.".to_string(),
+                };
+            }
+        }
+        KeyCode::Char('m') => {
+            let changed = app.mark_feed_read()?;
+            app.status = match changed {
+                0 => "Nothing unread.".to_string(),
+                1 => "Marked 1 entry as read.".to_string(),
+                _ => format!("Marked {changed} entries as read."),
+            };
+        }
+        KeyCode::Char('U') => {
+            app.toggle_unread_only();
+            app.status = if app.unread_only {
+                "Press U again to show every entry.".to_string()
+            } else {
+                "Showing all entries.".to_string()
+            };
+        }
+        KeyCode::Char('F') => refresh_selected(app, true)?,
+        KeyCode::Char('E') => {
+            if app.status.is_empty() {
+                app.status = "No status message to show.".to_string();
             } else {
-                app.status = "Select a feed to refresh.".to_string();
+                app.status_scroll = 0;
+                app.mode = Mode::ShowStatus;
+            }
+        }
+        KeyCode::Char('s') => {
+            if app.selected_feed().is_some() {
+                app.mode = Mode::FeedSettings;
+            } else {
+                app.status = "Select a feed to change its settings.".to_string();
+            }
+        }
+        KeyCode::Char('f') => {
+            if app.selected_feed().is_some() {
+                app.mode = Mode::FeedInfo;
+            } else {
+                app.status = "Select a feed to show its details.".to_string();
             }
         }
         KeyCode::Char('d') => {
@@ -81,14 +376,16 @@ fn handle_normal(app: &mut App, key: KeyEvent) -> Result<bool> {
                     if feed_index < app.db.feeds.len() {
                         let url = app.db.feeds[feed_index].url.clone();
                         app.db.feeds.remove(feed_index);
+                        app.selection_memory.remove(&Some(url.clone()));
                         if app.db.feeds.is_empty() {
                             app.feed_state.select(Some(0));
                             app.item_state.select(None);
                         } else {
                             let next = (feed_index + 1).min(app.db.feeds.len());
                             app.feed_state.select(Some(next));
+                            app.restore_selection();
                         }
-                        save_db(&app.db_path, &app.db)?;
+                        app.save()?;
                         app.status = format!("Removed {url}");
                     }
                 }
@@ -96,6 +393,12 @@ fn handle_normal(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
         KeyCode::Tab | KeyCode::Right => app.focus = Focus::Items,
         KeyCode::Left => app.focus = Focus::Feeds,
+        KeyCode::Up | KeyCode::Down if ctrl => {
+            let delta = if key.code == KeyCode::Up { -1 } else { 1 };
+            if !app.move_selected_feed(delta)? {
+                app.status = "Select a feed to move.".to_string();
+            }
+        }
         KeyCode::Up => app.move_selection(-1),
         KeyCode::Down => app.move_selection(1),
         KeyCode::PageUp => app.move_selection(-PAGE_JUMP),
@@ -106,3 +409,808 @@ fn handle_normal(app: &mut App, key: KeyEvent) -> Result<bool> {
     }
     Ok(false)
 }
+
+fn handle_feed_settings(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Char('o') => {
+            if let Some(preserve_order) = app.toggle_preserve_order()? {
+                app.status = if preserve_order {
+                    "Entries keep the feed's order.".to_string()
+                } else {
+                    "Entries are sorted by date.".to_string()
+                };
+            }
+        }
+        KeyCode::Char('c') => {
+            if let Some(clamp) = app.toggle_clamp_future_dates()? {
+                app.status = if clamp {
+                    "Future-dated entries sort as undated.".to_string()
+                } else {
+                    "Future-dated entries sort by their date.".to_string()
+                };
+            }
+        }
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('s') => app.mode = Mode::Normal,
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_set_priority(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+            app.input.clear();
+            app.status = "Priority unchanged.".to_string();
+        }
+        KeyCode::Enter => {
+            let input = std::mem::take(&mut app.input);
+            app.mode = Mode::Normal;
+            match input.trim().parse::<u8>() {
+                Ok(priority) => {
+                    if app.set_selected_priority(priority)? {
+                        app.status = format!("Priority set to {priority}.");
+                    }
+                }
+                Err(_) => app.status = "Priority must be a number from 0 to 255.".to_string(),
+            }
+        }
+        KeyCode::Backspace => {
+            app.input.pop();
+        }
+        KeyCode::Char(ch) if ch.is_ascii_digit() => app.input.push(ch),
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_import_path(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+            app.input.clear();
+            app.status = "Import cancelled.".to_string();
+        }
+        KeyCode::Enter => {
+            let input = std::mem::take(&mut app.input);
+            app.mode = Mode::Normal;
+            let path = input.trim();
+            if path.is_empty() {
+                app.status = "Path cannot be empty.".to_string();
+                return Ok(false);
+            }
+            app.status = match app.import_opml_file(Path::new(path)) {
+                Ok(summary) => format!(
+                    "Imported {} feeds ({} already subscribed); press R to fetch them.",
+                    summary.added.len(),
+                    summary.already_present
+                ),
+                Err(err) => format!("Error: {err:#}"),
+            };
+        }
+        KeyCode::Backspace => {
+            app.input.pop();
+        }
+        KeyCode::Char(ch) if !key.modifiers.contains(KeyModifiers::CONTROL) => app.input.push(ch),
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_export_path(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+            app.input.clear();
+            app.status = "Export cancelled.".to_string();
+        }
+        KeyCode::Enter => {
+            let input = std::mem::take(&mut app.input);
+            app.mode = Mode::Normal;
+            let path = input.trim();
+            if path.is_empty() {
+                app.status = "Path cannot be empty.".to_string();
+                return Ok(false);
+            }
+            app.status = match write_export(&app.db, Path::new(path), ExportFormat::Opml) {
+                Ok(count) => format!("Exported {count} feeds to {path}"),
+                Err(err) => format!("Error: {:#}", anyhow::Error::from(err)),
+            };
+        }
+        KeyCode::Backspace => {
+            app.input.pop();
+        }
+        KeyCode::Char(ch) if !key.modifiers.contains(KeyModifiers::CONTROL) => app.input.push(ch),
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn clear_item_selection(app: &mut App) {
+    if !app.selected_items.is_empty() {
+        app.selected_items.clear();
+        app.status = "Selection cleared.".to_string();
+    }
+}
+
+/// Shows the selected entry full screen and marks it read.
+fn open_reading_view(app: &mut App) -> Result<()> {
+    let Some(item) = app.selected_item() else {
+        app.status = "Select an entry to read.".to_string();
+        return Ok(());
+    };
+    if !item.read {
+        app.set_read(&item.source, true)?;
+    }
+    app.reading = Some(item.source);
+    app.reading_scroll = 0;
+    app.status.clear();
+    app.mode = Mode::Reading;
+    Ok(())
+}
+
+fn handle_reading(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let page = app.reading_page.saturating_sub(1).max(1);
+    // Scrolling past the end is clamped when the view is next drawn.
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.mode = Mode::Normal;
+            app.reading = None;
+            app.status.clear();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.reading_scroll = app.reading_scroll.saturating_add(1);
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.reading_scroll = app.reading_scroll.saturating_sub(1);
+        }
+        KeyCode::PageDown => app.reading_scroll = app.reading_scroll.saturating_add(page),
+        KeyCode::PageUp => app.reading_scroll = app.reading_scroll.saturating_sub(page),
+        KeyCode::Home => app.reading_scroll = 0,
+        KeyCode::End => app.reading_scroll = u16::MAX,
+        KeyCode::Char('o') => match app.reading_item() {
+            Some(item) => open_item_link(app, item)?,
+            None => app.status = "This entry is no longer stored.".to_string(),
+        },
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Opens the selected entry's link in the default browser without waiting
+/// for it and marks the entry read; a failure to launch is reported in the
+/// status line.
+fn open_selected_link(app: &mut App) -> Result<()> {
+    if app.focus != Focus::Items {
+        app.status = "Focus the entries to open one.".to_string();
+        return Ok(());
+    }
+    let Some(item) = app.selected_item() else {
+        app.status = "Select an entry to open.".to_string();
+        return Ok(());
+    };
+    open_item_link(app, item)
+}
+
+/// Opens the entry's link in the default browser and marks the entry read.
+fn open_item_link(app: &mut App, item: DisplayItem) -> Result<()> {
+    let Some(link) = item.link else {
+        app.status = format!("{} has no link.", item.title);
+        return Ok(());
+    };
+    let spawned = browser_command(&link)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(mut child) => {
+            // Reap the launcher in the background so it does not linger as a zombie.
+            thread::spawn(move || child.wait());
+            app.set_read(&item.source, true)?;
+            app.status = format!("Opened {link}");
+        }
+        Err(err) => app.status = format!("Error: failed to open {link}: {err}"),
+    }
+    Ok(())
+}
+
+fn browser_command(url: &str) -> Command {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(url);
+    command
+}
+
+/// Starts fetching the selected feed in the background; the result is stored
+/// by [`poll_refresh`] when it arrives.
+fn refresh_selected(app: &mut App, force: bool) -> Result<()> {
+    if refresh_running(app) {
+        return Ok(());
+    }
+    let Some(feed) = app.selected_feed() else {
+        app.status = "Select a feed to refresh.".to_string();
+        return Ok(());
+    };
+    let url = feed.url.clone();
+    if let Some(until) = rate_limited_until(feed) {
+        app.status = format!(
+            "{url} is rate limited until {}; skipping.",
+            until.to_rfc3339()
+        );
+        return Ok(());
+    }
+    // A forced refresh replaces the stored entries, so it needs the full feed.
+    let validators = if force {
+        CacheValidators::default()
+    } else {
+        feed.cache_validators()
+    };
+    let label = format!("Refreshing {}", host_label(&url));
+    let options = app.fetch_options;
+    start_fetch(app, label, async move {
+        let result = fetch_feed_pages(&url, 1, &validators, &options).await;
+        RefreshDone::Feed { url, force, result }
+    });
+    Ok(())
+}
+
+/// Starts fetching every feed that is not rate limited in the background, at
+/// most `app.jobs` at a time and highest priority first.
+fn refresh_all(app: &mut App) -> Result<()> {
+    if app.db.feeds.is_empty() {
+        app.status = "No feeds to refresh.".to_string();
+        return Ok(());
+    }
+    if refresh_running(app) {
+        return Ok(());
+    }
+    let mut feeds: Vec<&FeedRecord> = app
+        .db
+        .feeds
+        .iter()
+        .filter(|feed| rate_limited_until(feed).is_none())
+        .collect();
+    let skipped = app.db.feeds.len() - feeds.len();
+    feeds.sort_by_key(|feed| std::cmp::Reverse(feed.priority));
+    let requests: Vec<_> = feeds
+        .iter()
+        .map(|feed| (feed.url.clone(), feed.cache_validators()))
+        .collect();
+    let label = format!("Refreshing {} feeds", requests.len());
+    let jobs = app.jobs;
+    let options = app.fetch_options;
+    start_fetch(app, label, async move {
+        let results = fetch_all(requests, jobs, options).await;
+        RefreshDone::All { results, skipped }
+    });
+    Ok(())
+}
+
+/// Runs `fetch` on the runtime and hands its result to [`poll_refresh`];
+/// `label` is shown in the status line until then.
+fn start_fetch<F>(app: &mut App, label: String, fetch: F)
+where
+    F: Future<Output = RefreshDone> + Send + 'static,
+{
+    let sender = app.refresh_sender.clone();
+    let task = app.runtime.spawn(async move {
+        // Nobody is listening once the TUI has quit; the result is dropped.
+        let _ = sender.send(fetch.await);
+    });
+    app.refresh_task = Some(task.abort_handle());
+    app.refreshing = Some((label, Instant::now()));
+    app.status.clear();
+}
+
+/// The host of `url` for the status line, or all of it when it has none.
+fn host_label(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Only one refresh runs at a time, so results land in the order they were
+/// asked for.
+fn refresh_running(app: &mut App) -> bool {
+    if app.refreshing.is_some() {
+        app.status = "A refresh is already running.".to_string();
+    }
+    app.refreshing.is_some()
+}
+
+/// Stores a background refresh that has finished, if any, and saves the db.
+/// Returns true when the screen needs redrawing.
+pub(super) fn poll_refresh(app: &mut App) -> Result<bool> {
+    let Ok(done) = app.refresh_results.try_recv() else {
+        return Ok(false);
+    };
+    app.refreshing = None;
+    app.refresh_task = None;
+    if matches!(app.mode, Mode::ConfirmQuit) {
+        // Nothing is left to wait for.
+        app.mode = Mode::Normal;
+    }
+    match done {
+        RefreshDone::Feed { url, force, result } => finish_refresh(app, url, force, result)?,
+        RefreshDone::All { results, skipped } => finish_refresh_all(app, results, skipped)?,
+        RefreshDone::Add { url, result } => finish_add(app, url, result),
+    }
+    Ok(true)
+}
+
+fn finish_refresh(
+    app: &mut App,
+    url: String,
+    force: bool,
+    result: Result<FetchedFeed, FetchError>,
+) -> Result<()> {
+    if !app.db.feeds.iter().any(|feed| feed.url == url) {
+        app.status = format!("{url} was deleted before its refresh finished.");
+        return Ok(());
+    }
+    match result {
+        Ok((meta, items)) => {
+            // An empty feed keeps the stored entries unless the refresh was forced.
+            let kept = items.is_empty()
+                && !force
+                && app.db.feeds.iter().any(|feed| feed.url == url && !feed.items.is_empty());
+            let previous = app.selected_item_ref();
+            app.store_refreshed_feed(url.clone(), meta, items, force);
+            app.reselect_item(previous);
+            app.save()?;
+            let feed = app.db.feeds.iter().find(|feed| feed.url == url);
+            app.status = with_warnings(format!("Refreshed {url}"), feed);
+            if kept {
+                app.status.push_str(" Press F to clear the kept entries.");
+            }
+        }
+        Err(FetchError::NotModified { validators, .. }) => {
+            app.record_not_modified(&url, validators);
+            app.save()?;
+            app.status = format!("Not modified: {url}");
+        }
+        Err(FetchError::RateLimited {
+            retry_after_secs, ..
+        }) => {
+            let wait =
+                Duration::seconds(retry_after_secs.unwrap_or(DEFAULT_RATE_LIMIT_SECS) as i64);
+            let until = Utc::now() + wait;
+            app.record_rate_limit(&url, until);
+            app.save()?;
+            app.status = format!("{url} is rate limited until {}.", until.to_rfc3339());
+        }
+        Err(err) => {
+            let http_status = err.http_status();
+            let err = anyhow::Error::from(err);
+            app.status = format!("Error: {err:#}");
+            app.record_feed_error(&url, format!("{err:#}"), http_status);
+            app.save()?;
+        }
+    }
+    Ok(())
+}
+
+/// Stores the results of a refresh of all feeds without moving the
+/// selection. One feed failing does not stop the others; the db is saved once.
+fn finish_refresh_all(
+    app: &mut App,
+    results: Vec<(String, Result<FetchedFeed, FetchError>)>,
+    skipped: usize,
+) -> Result<()> {
+    let previous = app.selected_item_ref();
+    app.refresh_errors.clear();
+    let mut refreshed = 0;
+    let mut unchanged = 0;
+    for (url, result) in results {
+        if !app.db.feeds.iter().any(|feed| feed.url == url) {
+            // Deleted while the refresh was running.
+            continue;
+        }
+        match result {
+            Ok((meta, items)) => {
+                app.store_refreshed_feed(url, meta, items, false);
+                refreshed += 1;
+            }
+            Err(FetchError::NotModified { validators, .. }) => {
+                app.record_not_modified(&url, validators);
+                refreshed += 1;
+                unchanged += 1;
+            }
+            Err(FetchError::RateLimited {
+                retry_after_secs, ..
+            }) => {
+                let wait =
+                    Duration::seconds(retry_after_secs.unwrap_or(DEFAULT_RATE_LIMIT_SECS) as i64);
+                let until = Utc::now() + wait;
+                app.record_rate_limit(&url, until);
+                app.refresh_errors
+                    .push(format!("{url} is rate limited until {}", until.to_rfc3339()));
+            }
+            Err(err) => {
+                let http_status = err.http_status();
+                let err = format!("{:#}", anyhow::Error::from(err));
+                app.record_feed_error(&url, err.clone(), http_status);
+                app.refresh_errors.push(err);
+            }
+        }
+    }
+    app.reselect_item(previous);
+    app.save()?;
+
+    let errors = app.refresh_errors.len();
+    let mut status = format!("Refreshed {refreshed} feeds");
+    if unchanged > 0 {
+        status.push_str(&format!(" ({unchanged} not modified)"));
+    }
+    status.push_str(&format!(", {errors} failed"));
+    if skipped > 0 {
+        status.push_str(&format!(", {skipped} skipped while rate limited"));
+    }
+    if errors > 0 {
+        status.push_str(" (E for details)");
+    }
+    app.status = status;
+    Ok(())
+}
+
+/// Returns the time a stored rate limit expires, if it is still in effect.
+fn rate_limited_until(feed: &FeedRecord) -> Option<DateTime<Utc>> {
+    let until = DateTime::parse_from_rfc3339(feed.rate_limited_until.as_deref()?).ok()?;
+    let until = until.with_timezone(&Utc);
+    (until > Utc::now()).then_some(until)
+}
+
+fn with_warnings(message: String, feed: Option<&FeedRecord>) -> String {
+    let warnings = feed.map(|feed| feed.warnings.as_slice()).unwrap_or_default();
+    match warnings {
+        [] => message,
+        [only] => format!("{message} (warning: {only})"),
+        [first, rest @ ..] => format!("{message} (warning: {first}; {} more)", rest.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration as StdDuration;
+
+    use super::*;
+    use crate::db::{Backfill, FeedDb};
+    use crate::test_util::{item, response, rss, serve};
+
+    fn press(app: &mut App, code: KeyCode) {
+        assert!(!handle_key(app, KeyEvent::from(code)).unwrap());
+    }
+
+    fn add_url(app: &mut App, url: &str) {
+        press(app, KeyCode::Char('a'));
+        for ch in url.chars() {
+            press(app, KeyCode::Char(ch));
+        }
+        press(app, KeyCode::Enter);
+    }
+
+    fn wait_for_refresh(app: &mut App) {
+        let started = Instant::now();
+        while !poll_refresh(app).unwrap() {
+            assert!(started.elapsed() < StdDuration::from_secs(10), "refresh never finished");
+            thread::sleep(StdDuration::from_millis(5));
+        }
+    }
+
+    /// An app subscribed to one stored entry of a served two-entry feed.
+    fn subscribed() -> (App, String) {
+        let url = format!("{}/feed.xml", serve(|_| response("200 OK", &[], &rss(&["one", "two"]))));
+        let mut db = FeedDb::default();
+        let items = vec![item("one", "https://example.com/one")];
+        db.upsert_feed(url.clone(), FeedMeta::default(), items, false, true);
+        (App::loaded(db), url)
+    }
+
+    #[test]
+    fn adding_a_differently_spelled_duplicate_asks_first() {
+        let (mut app, url) = subscribed();
+        let spelling = format!("{url}/#latest");
+        add_url(&mut app, &spelling);
+
+        assert!(matches!(app.mode, Mode::ConfirmDuplicate));
+        let pending = app.pending_add.as_ref().unwrap();
+        assert_eq!(pending.url, spelling);
+        assert_eq!(pending.existing_url, url);
+    }
+
+    #[test]
+    fn adding_a_new_feed_skips_the_prompt() {
+        let (mut app, url) = subscribed();
+        add_url(&mut app, &url.replace("feed.xml", "other.xml"));
+        wait_for_refresh(&mut app);
+
+        assert!(matches!(app.mode, Mode::AddPreview));
+        assert!(app.pending_add.is_none());
+    }
+
+    #[test]
+    fn confirming_with_r_refreshes_the_existing_feed() {
+        let (mut app, url) = subscribed();
+        add_url(&mut app, &format!("{url}/"));
+        press(&mut app, KeyCode::Char('r'));
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.selected_feed().map(|feed| feed.url.as_str()), Some(url.as_str()));
+        assert!(app.refreshing.is_some());
+        wait_for_refresh(&mut app);
+        assert_eq!(app.status, format!("Refreshed {url}"));
+        assert_eq!(app.db.feeds.len(), 1);
+        assert_eq!(app.db.feeds[0].items.len(), 2);
+    }
+
+    #[test]
+    fn confirming_with_k_adds_the_new_spelling() {
+        let (mut app, url) = subscribed();
+        let spelling = format!("{url}/");
+        add_url(&mut app, &spelling);
+        press(&mut app, KeyCode::Char('k'));
+        wait_for_refresh(&mut app);
+
+        assert!(matches!(app.mode, Mode::AddPreview));
+        assert_eq!(app.add_preview.as_ref().unwrap().url, spelling);
+        press(&mut app, KeyCode::Enter);
+        let urls: Vec<_> = app.db.feeds.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(urls, [url.as_str(), spelling.as_str()]);
+    }
+
+    #[test]
+    fn confirming_the_exact_url_with_k_edits_it() {
+        let (mut app, url) = subscribed();
+        add_url(&mut app, &url);
+        press(&mut app, KeyCode::Char('k'));
+
+        assert!(matches!(app.mode, Mode::AddUrl));
+        assert_eq!(app.input, url);
+        assert_eq!(app.status, "Edit the URL to keep both feeds.");
+        assert_eq!(app.db.feeds.len(), 1);
+    }
+
+    #[test]
+    fn escape_cancels_the_duplicate_add() {
+        let (mut app, url) = subscribed();
+        add_url(&mut app, &url);
+        press(&mut app, KeyCode::Char('x'));
+        assert!(matches!(app.mode, Mode::ConfirmDuplicate));
+        press(&mut app, KeyCode::Esc);
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.pending_add.is_none());
+        assert_eq!(app.status, "Add cancelled.");
+        assert_eq!(app.db.feeds.len(), 1);
+        assert_eq!(app.db.feeds[0].items.len(), 1);
+    }
+
+    #[test]
+    fn unchanged_feed_refreshes_as_not_modified() {
+        let url = serve(|request| {
+            if request.to_ascii_lowercase().contains("if-none-match: \"v1\"") {
+                response("304 Not Modified", &["ETag: \"v1\""], "")
+            } else {
+                response("200 OK", &["ETag: \"v1\""], &rss(&["one", "two"]))
+            }
+        });
+        let mut db = FeedDb::default();
+        db.add_feed(url.clone(), FeedMeta::default(), Vec::new(), Backfill::All);
+        let mut app = App::loaded(db);
+        app.select_feed(1);
+
+        refresh_selected(&mut app, false).unwrap();
+        wait_for_refresh(&mut app);
+        assert_eq!(app.status, format!("Refreshed {url}"));
+        assert_eq!(app.db.feeds[0].etag.as_deref(), Some("\"v1\""));
+        app.db.feeds[0].items[0].read = true;
+
+        refresh_selected(&mut app, false).unwrap();
+        wait_for_refresh(&mut app);
+        assert_eq!(app.status, format!("Not modified: {url}"));
+        assert_eq!(app.db.feeds[0].last_http_status, Some(304));
+        assert_eq!(app.db.feeds[0].items.len(), 2);
+        assert!(app.db.feeds[0].items[0].read);
+    }
+
+    #[test]
+    fn empty_feed_keeps_entries_until_a_forced_refresh() {
+        let url = serve(|_| response("200 OK", &[], &rss(&[])));
+        let mut db = FeedDb::default();
+        let items = vec![item("one", "https://example.com/one")];
+        db.upsert_feed(url.clone(), FeedMeta::default(), items, false, true);
+        let mut app = App::loaded(db);
+        app.select_feed(1);
+
+        press(&mut app, KeyCode::Char('r'));
+        wait_for_refresh(&mut app);
+        assert_eq!(
+            app.status,
+            format!(
+                "Refreshed {url} (warning: feed returned 0 entries; kept 1 stored items) \
+                 Press F to clear the kept entries."
+            )
+        );
+        assert_eq!(app.db.feeds[0].items.len(), 1);
+
+        press(&mut app, KeyCode::Char('F'));
+        wait_for_refresh(&mut app);
+        assert_eq!(app.status, format!("Refreshed {url}"));
+        assert!(app.db.feeds[0].items.is_empty());
+    }
+
+    #[test]
+    fn enter_subscribes_from_the_preview() {
+        let url = serve(|_| response("200 OK", &[], &rss(&["one", "two"])));
+        let mut app = App::loaded(FeedDb::default());
+        add_url(&mut app, &url);
+
+        assert!(matches!(app.mode, Mode::Normal), "the fetch runs in the background");
+        assert!(app.refreshing.is_some());
+        wait_for_refresh(&mut app);
+        assert!(matches!(app.mode, Mode::AddPreview));
+        assert!(!app.db_path.exists(), "nothing is saved before confirming");
+        press(&mut app, KeyCode::Enter);
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.add_preview.is_none());
+        assert_eq!(app.status, format!("Added {url}"));
+        let saved = crate::db::load_db(&app.db_path).unwrap();
+        assert_eq!(saved.feeds.len(), 1);
+        assert_eq!(saved.feeds[0].items.len(), 2);
+    }
+
+    #[test]
+    fn escape_discards_the_preview() {
+        let url = serve(|_| response("200 OK", &[], &rss(&["one"])));
+        let mut app = App::loaded(FeedDb::default());
+        add_url(&mut app, &url);
+        wait_for_refresh(&mut app);
+        press(&mut app, KeyCode::Char('x'));
+        assert!(matches!(app.mode, Mode::AddPreview));
+        press(&mut app, KeyCode::Esc);
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.add_preview.is_none());
+        assert_eq!(app.status, format!("Discarded {url}"));
+        assert!(app.db.feeds.is_empty());
+        assert!(!app.db_path.exists());
+    }
+
+    #[test]
+    fn failed_fetch_never_shows_a_preview() {
+        let url = serve(|_| response("404 Not Found", &[], ""));
+        let mut app = App::loaded(FeedDb::default());
+        add_url(&mut app, &url);
+        wait_for_refresh(&mut app);
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.add_preview.is_none());
+        assert!(app.status.starts_with("Error: "), "{}", app.status);
+    }
+
+    #[test]
+    fn adding_during_a_refresh_keeps_the_url() {
+        let (mut app, url) = subscribed();
+        app.select_feed(1);
+        press(&mut app, KeyCode::Char('r'));
+        let other = url.replace("feed.xml", "other.xml");
+        add_url(&mut app, &other);
+
+        assert!(matches!(app.mode, Mode::AddUrl));
+        assert_eq!(app.input, other);
+        assert_eq!(app.status, "A refresh is already running.");
+        wait_for_refresh(&mut app);
+        press(&mut app, KeyCode::Enter);
+        wait_for_refresh(&mut app);
+        assert!(matches!(app.mode, Mode::AddPreview));
+    }
+
+    #[test]
+    fn preview_waits_for_an_open_prompt() {
+        let url = serve(|_| response("200 OK", &[], &rss(&["one"])));
+        let mut app = App::loaded(FeedDb::default());
+        add_url(&mut app, &url);
+        press(&mut app, KeyCode::Char('i'));
+        wait_for_refresh(&mut app);
+
+        assert!(matches!(app.mode, Mode::ImportPath));
+        assert!(app.add_preview.is_none());
+        assert_eq!(app.status, format!("Fetched {url}; add it again to preview it."));
+    }
+
+    /// An app subscribed to a feed whose server holds each response until the
+    /// returned sender releases it.
+    fn gated() -> (App, mpsc::Sender<()>) {
+        let (release, gate) = mpsc::channel();
+        let url = serve(move |_| {
+            let _ = gate.recv();
+            response("200 OK", &[], &rss(&["one", "two"]))
+        });
+        let mut db = FeedDb::default();
+        db.upsert_feed(url, FeedMeta::default(), Vec::new(), false, true);
+        let mut app = App::loaded(db);
+        app.select_feed(1);
+        (app, release)
+    }
+
+    fn quits(app: &mut App, code: KeyCode) -> bool {
+        handle_key(app, KeyEvent::from(code)).unwrap()
+    }
+
+    #[test]
+    fn quitting_with_nothing_running_skips_the_prompt() {
+        let (mut app, _) = subscribed();
+        assert!(quits(&mut app, KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn quit_anyway_leaves_the_fetch_behind() {
+        let (mut app, _release) = gated();
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Char('q'));
+
+        assert!(matches!(app.mode, Mode::ConfirmQuit));
+        let task = app.refresh_task.clone().unwrap();
+        assert!(quits(&mut app, KeyCode::Char('q')));
+        assert!(!task.is_finished());
+    }
+
+    #[test]
+    fn waiting_quits_once_the_refresh_is_stored() {
+        let (mut app, release) = gated();
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Char('q'));
+        press(&mut app, KeyCode::Char('w'));
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.refresh_indicator().unwrap().ends_with("(quitting when done)"));
+        assert!(!app.ready_to_quit());
+        release.send(()).unwrap();
+        wait_for_refresh(&mut app);
+        assert!(app.ready_to_quit());
+        assert_eq!(crate::db::load_db(&app.db_path).unwrap().feeds[0].items.len(), 2);
+    }
+
+    #[test]
+    fn cancelling_stops_the_fetch_and_quits() {
+        let (mut app, _release) = gated();
+        press(&mut app, KeyCode::Char('r'));
+        let task = app.refresh_task.clone().unwrap();
+        press(&mut app, KeyCode::Char('q'));
+
+        assert!(quits(&mut app, KeyCode::Char('c')));
+        let started = Instant::now();
+        while !task.is_finished() {
+            assert!(started.elapsed() < StdDuration::from_secs(10), "fetch never stopped");
+            thread::sleep(StdDuration::from_millis(5));
+        }
+        assert!(app.refresh_results.try_recv().is_err(), "a cancelled fetch reports nothing");
+    }
+
+    #[test]
+    fn escape_keeps_the_fetch_and_the_app_running() {
+        let (mut app, release) = gated();
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Char('q'));
+        press(&mut app, KeyCode::Esc);
+
+        assert!(matches!(app.mode, Mode::Normal));
+        press(&mut app, KeyCode::Char('q'));
+        release.send(()).unwrap();
+        wait_for_refresh(&mut app);
+        assert!(matches!(app.mode, Mode::Normal), "the prompt closes once nothing runs");
+        assert!(!app.ready_to_quit());
+        assert_eq!(app.db.feeds[0].items.len(), 2);
+    }
+}
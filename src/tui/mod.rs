@@ -1,45 +1,311 @@
 use std::io::{self, Stdout};
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use crossterm::event::{self, Event};
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::{execute, terminal};
 use ratatui::prelude::*;
 
+use crate::db::{check_db_path, DbAccess, DEFAULT_METADATA_INTERVAL_DAYS};
+use crate::feed::{FetchOptions, DEFAULT_JOBS};
+
 mod input;
+mod simple_ui;
 mod state;
+mod theme;
 mod ui;
 
-pub fn run_tui(db_path: PathBuf) -> Result<()> {
+pub const DEFAULT_TICK_MS: u64 = 250;
+pub const DEFAULT_INPUT_POLL_MS: u64 = 50;
+
+/// Startup options for the TUI, resolved from the command line.
+pub struct TuiOptions {
+    pub no_color: bool,
+    pub tick_ms: u64,
+    pub input_poll_ms: u64,
+    pub mouse: bool,
+    pub create_dirs: bool,
+    pub simple: bool,
+    pub metadata_interval_days: u32,
+    /// Feeds fetched at the same time by a refresh of all feeds.
+    pub jobs: u32,
+    /// Start with this feed selected and its newest entry focused.
+    pub feed: Option<String>,
+    /// Timeout and retries of every fetch.
+    pub fetch: FetchOptions,
+}
+
+impl Default for TuiOptions {
+    fn default() -> Self {
+        Self {
+            no_color: false,
+            tick_ms: DEFAULT_TICK_MS,
+            input_poll_ms: DEFAULT_INPUT_POLL_MS,
+            mouse: false,
+            create_dirs: false,
+            simple: false,
+            metadata_interval_days: DEFAULT_METADATA_INTERVAL_DAYS,
+            jobs: DEFAULT_JOBS,
+            feed: None,
+            fetch: FetchOptions::default(),
+        }
+    }
+}
+
+pub fn run_tui(db_path: PathBuf, options: TuiOptions) -> Result<()> {
+    let access = check_db_path(&db_path, options.create_dirs)?;
+    let read_only = access == DbAccess::ReadOnly;
+    let start = state::StartView { feed: options.feed };
+    let theme = theme::Theme::detect(options.no_color);
+    let mut app = state::App::new(db_path, theme, read_only, start)?;
+    app.simple = options.simple;
+    app.metadata_interval = chrono::Duration::days(i64::from(options.metadata_interval_days));
+    app.jobs = options.jobs as usize;
+    app.fetch_options = options.fetch;
+
     enable_raw_mode().context("failed to enable raw mode")?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
+    if options.mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     terminal::enable_raw_mode()?;
 
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
-    let mut app = state::App::new(db_path)?;
+    let mut events = EventLoop::new(
+        TerminalEvents,
+        Duration::from_millis(options.input_poll_ms),
+        Duration::from_millis(options.tick_ms),
+    );
 
-    let result = run_app(&mut terminal, &mut app);
+    let result = run_app(&mut terminal, &mut app, &mut events, options.mouse);
 
     disable_raw_mode().ok();
+    if options.mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture).ok();
+    }
     execute!(terminal.backend_mut(), LeaveAlternateScreen).ok();
     terminal.show_cursor().ok();
 
     result
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut state::App) -> Result<()> {
+fn run_app<S: EventSource>(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut state::App,
+    events: &mut EventLoop<S>,
+    mouse: bool,
+) -> Result<()> {
+    let mut redraw = true;
     loop {
-        terminal.draw(|frame| ui::draw_ui(frame, app))?;
+        redraw |= app.poll_load()?;
+        redraw |= input::poll_refresh(app)?;
+        if app.ready_to_quit() {
+            return Ok(());
+        }
+        if redraw {
+            app.reconcile_selection();
+            terminal.draw(|frame| {
+                if matches!(app.mode, state::Mode::Loading | state::Mode::LoadFailed) {
+                    ui::draw_loading(frame, app);
+                } else if matches!(app.mode, state::Mode::Reading) {
+                    ui::draw_reading(frame, app);
+                } else if app.simple {
+                    simple_ui::draw_simple_ui(frame, app);
+                } else {
+                    ui::draw_ui(frame, app);
+                }
+            })?;
+        }
 
-        if event::poll(Duration::from_millis(200))? {
-            if let Event::Key(key) = event::read()? {
+        redraw = match events.next(Instant::now())? {
+            Some(LoopEvent::Input(Event::Key(key))) => {
                 if input::handle_key(app, key)? {
                     return Ok(());
                 }
+                true
             }
+            Some(LoopEvent::Input(Event::Mouse(event))) => {
+                input::handle_mouse(app, event);
+                true
+            }
+            Some(LoopEvent::Input(_)) | Some(LoopEvent::Tick) => true,
+            None => false,
+        };
+
+        if app.suspend_requested {
+            app.suspend_requested = false;
+            suspend(terminal, mouse)?;
+        }
+    }
+}
+
+/// Where the event loop reads terminal input from; tests can substitute a
+/// scripted source.
+trait EventSource {
+    fn poll(&mut self, timeout: Duration) -> Result<bool>;
+    fn read(&mut self) -> Result<Event>;
+}
+
+struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> Result<bool> {
+        Ok(event::poll(timeout)?)
+    }
+
+    fn read(&mut self) -> Result<Event> {
+        Ok(event::read()?)
+    }
+}
+
+enum LoopEvent {
+    Input(Event),
+    Tick,
+}
+
+/// Separates input latency from the redraw tick: input is polled with a short
+/// timeout, while time-based work only happens once per tick. The current
+/// time is passed in so a fake clock can drive it.
+struct EventLoop<S> {
+    source: S,
+    input_poll: Duration,
+    tick: Duration,
+    last_tick: Option<Instant>,
+}
+
+impl<S: EventSource> EventLoop<S> {
+    fn new(source: S, input_poll: Duration, tick: Duration) -> Self {
+        Self {
+            source,
+            input_poll,
+            tick,
+            last_tick: None,
+        }
+    }
+
+    fn next(&mut self, now: Instant) -> Result<Option<LoopEvent>> {
+        if self.last_tick.is_none_or(|last| now.duration_since(last) >= self.tick) {
+            self.last_tick = Some(now);
+            return Ok(Some(LoopEvent::Tick));
+        }
+        if self.source.poll(self.input_poll)? {
+            return Ok(Some(LoopEvent::Input(self.source.read()?)));
         }
+        Ok(None)
+    }
+}
+
+/// Hands the terminal back to the shell, stops the process with SIGTSTP and
+/// restores the TUI once the shell resumes it with SIGCONT.
+#[cfg(unix)]
+fn suspend(terminal: &mut Terminal<CrosstermBackend<Stdout>>, mouse: bool) -> Result<()> {
+    disable_raw_mode().context("failed to disable raw mode")?;
+    if mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)
+        .context("failed to suspend process")?;
+
+    enable_raw_mode().context("failed to enable raw mode")?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    if mouse {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
+    terminal.clear()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn suspend(_terminal: &mut Terminal<CrosstermBackend<Stdout>>, _mouse: bool) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use super::*;
+
+    /// Hands out queued events and records how long each poll would wait.
+    #[derive(Default)]
+    struct Scripted {
+        events: VecDeque<Event>,
+        polls: Vec<Duration>,
+    }
+
+    impl EventSource for Scripted {
+        fn poll(&mut self, timeout: Duration) -> Result<bool> {
+            self.polls.push(timeout);
+            Ok(!self.events.is_empty())
+        }
+
+        fn read(&mut self) -> Result<Event> {
+            Ok(self.events.pop_front().expect("read without a pending event"))
+        }
+    }
+
+    fn key(code: char) -> Event {
+        Event::Key(KeyEvent::from(KeyCode::Char(code)))
+    }
+
+    fn event_loop(events: Vec<Event>) -> EventLoop<Scripted> {
+        let source = Scripted {
+            events: events.into(),
+            polls: Vec::new(),
+        };
+        EventLoop::new(source, Duration::from_millis(50), Duration::from_millis(250))
+    }
+
+    fn pressed(event: Option<LoopEvent>) -> Option<char> {
+        match event {
+            Some(LoopEvent::Input(Event::Key(KeyEvent { code: KeyCode::Char(c), .. }))) => Some(c),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn ticks_first_then_delivers_input_in_order() {
+        let start = Instant::now();
+        let mut events = event_loop(vec![key('a'), key('b')]);
+
+        assert!(matches!(events.next(start).unwrap(), Some(LoopEvent::Tick)));
+        assert_eq!(pressed(events.next(start).unwrap()), Some('a'));
+        assert_eq!(pressed(events.next(start + Duration::from_millis(10)).unwrap()), Some('b'));
+        assert!(events.next(start + Duration::from_millis(20)).unwrap().is_none());
+        assert!(events.source.polls.iter().all(|&poll| poll == Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn ticks_once_per_interval_of_the_fake_clock() {
+        let start = Instant::now();
+        let mut events = event_loop(Vec::new());
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let ticks: Vec<bool> = [0, 100, 249, 250, 300, 499, 500, 1000]
+            .into_iter()
+            .map(|ms| matches!(events.next(at(ms)).unwrap(), Some(LoopEvent::Tick)))
+            .collect();
+        assert_eq!(ticks, [true, false, false, true, false, false, true, true]);
+    }
+
+    #[test]
+    fn due_tick_comes_before_pending_input() {
+        let start = Instant::now();
+        let mut events = event_loop(vec![key('q')]);
+
+        assert!(matches!(events.next(start).unwrap(), Some(LoopEvent::Tick)));
+        let late = start + Duration::from_millis(400);
+        assert!(matches!(events.next(late).unwrap(), Some(LoopEvent::Tick)));
+        assert_eq!(pressed(events.next(late).unwrap()), Some('q'));
+        // Input handling did not poll while a tick was due.
+        assert_eq!(events.source.polls.len(), 1);
     }
 }
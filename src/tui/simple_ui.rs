@@ -0,0 +1,301 @@
+use chrono::Utc;
+use ratatui::prelude::*;
+use ratatui::widgets::{List, ListItem, Paragraph, Wrap};
+
+use super::state::{App, DisplayItem, Focus, Mode};
+use super::ui::{draw_popup, mode_prompt};
+
+/// One line of the simple view: a feed header, or one of that feed's entries.
+#[derive(Clone, Copy, PartialEq)]
+struct Row {
+    feed: usize,
+    item: Option<usize>,
+}
+
+/// Feed headers each followed by their entries, in the order they are drawn.
+fn rows(app: &App) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (feed, record) in app.db.feeds.iter().enumerate() {
+        rows.push(Row { feed, item: None });
+        let count = app.feed_view_items(record, Utc::now()).len();
+        rows.extend((0..count).map(|item| Row {
+            feed,
+            item: Some(item),
+        }));
+    }
+    rows
+}
+
+/// The row matching the App's feed and entry selection. Focus on the feeds
+/// pane means the feed header is selected.
+fn current_row(app: &App, rows: &[Row]) -> usize {
+    let feed = app
+        .feed_state
+        .selected()
+        .and_then(|index| index.checked_sub(1))
+        .unwrap_or(0);
+    let item = match app.focus {
+        Focus::Items => app.item_state.selected(),
+        Focus::Feeds => None,
+    };
+    rows.iter()
+        .position(|row| *row == Row { feed, item })
+        .or_else(|| rows.iter().position(|row| row.feed == feed))
+        .unwrap_or(0)
+}
+
+/// Moves through the linear list, keeping the App's feed and entry selection
+/// in step so every other action works on what the simple view shows.
+pub(super) fn move_row(app: &mut App, delta: isize) {
+    let rows = rows(app);
+    if rows.is_empty() {
+        return;
+    }
+    let last = rows.len() as isize - 1;
+    let next = (current_row(app, &rows) as isize + delta).clamp(0, last) as usize;
+    let row = rows[next];
+    app.feed_state.select(Some(row.feed + 1));
+    app.item_state.select(row.item);
+    app.focus = if row.item.is_some() {
+        Focus::Items
+    } else {
+        Focus::Feeds
+    };
+    app.status.clear();
+}
+
+/// Describes the selected row in words; `full` adds the link and warnings.
+pub(super) fn describe_selection(app: &App, full: bool) -> String {
+    let rows = rows(app);
+    if rows.is_empty() {
+        return "No feeds. Press a to add one, q to quit.".to_string();
+    }
+    let row = rows[current_row(app, &rows)];
+    let feed = &app.db.feeds[row.feed];
+    let items = app.feed_view_items(feed, Utc::now());
+    let mut text = match row.item.and_then(|index| items.get(index).map(|item| (index, item))) {
+        Some((index, item)) => {
+            let mut text = describe_item(item, index, items.len());
+            if full && let Some(link) = &item.link {
+                text.push_str(&format!(", link {link}"));
+            }
+            text
+        }
+        None => {
+            let mut text = describe_feed(app, row.feed);
+            if full {
+                text.push_str(&format!(", address {}", feed.url));
+                for warning in &feed.warnings {
+                    text.push_str(&format!(", warning: {warning}"));
+                }
+            }
+            text
+        }
+    };
+    if full {
+        text.push('.');
+    }
+    text
+}
+
+fn describe_feed(app: &App, index: usize) -> String {
+    let feed = &app.db.feeds[index];
+    let mut text = format!(
+        "Feed {} of {}: {}, {} entries, {} unread",
+        index + 1,
+        app.db.feeds.len(),
+        feed.title.as_deref().unwrap_or("Untitled"),
+        feed.items.len(),
+        feed.unread_count()
+    );
+    if !feed.warnings.is_empty() {
+        text.push_str(&format!(", {} warnings", feed.warnings.len()));
+    }
+    if feed.last_error.is_some() {
+        text.push_str(", last refresh failed");
+    }
+    text
+}
+
+fn describe_item(item: &DisplayItem, index: usize, count: usize) -> String {
+    let mut text = format!("Entry {} of {}: {}", index + 1, count, item.title);
+    if let Some(author) = &item.author {
+        text.push_str(&format!(" by {author}"));
+    }
+    if !item.read {
+        text.push_str(", unread");
+    }
+    match &item.published {
+        Some(published) if item.future => text.push_str(&format!(", dated {published} (future)")),
+        Some(published) => text.push_str(&format!(", published {published}")),
+        None => text.push_str(", no date"),
+    }
+    text
+}
+
+pub(super) fn draw_simple_ui(frame: &mut Frame, app: &mut App) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(2)])
+        .split(frame.size());
+
+    let error_count = app.db.feeds.iter().filter(|feed| feed.last_error.is_some()).count();
+    let mut header = "rss-cli simple view".to_string();
+    if app.read_only {
+        header.push_str(", read-only");
+    }
+    if app.unread_only {
+        header.push_str(", unread entries only");
+    }
+    if error_count > 0 {
+        header.push_str(&format!(", {error_count} feeds with errors"));
+    }
+    frame.render_widget(Paragraph::new(header), layout[0]);
+
+    let rows = rows(app);
+    let now = Utc::now();
+    let mut lines = Vec::with_capacity(rows.len());
+    for (index, feed) in app.db.feeds.iter().enumerate() {
+        lines.push(ListItem::new(describe_feed(app, index)));
+        let items = app.feed_view_items(feed, now);
+        let count = items.len();
+        for (position, item) in items.iter().enumerate() {
+            lines.push(ListItem::new(format!("  {}", describe_item(item, position, count))));
+        }
+    }
+    let selected = (!rows.is_empty()).then(|| current_row(app, &rows));
+    app.simple_list.select(selected);
+    let list = List::new(lines).highlight_symbol("> ");
+    frame.render_stateful_widget(list, layout[1], &mut app.simple_list);
+
+    let status = match app.mode {
+        Mode::Normal if let Some(indicator) = app.refresh_indicator() => indicator,
+        Mode::Normal if app.status.is_empty() => describe_selection(app, false),
+        _ => mode_prompt(app),
+    };
+    frame.render_widget(Paragraph::new(status).wrap(Wrap { trim: true }), layout[2]);
+
+    draw_popup(frame, app);
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::db::{FeedDb, FeedItem};
+    use crate::feed::FeedMeta;
+    use crate::test_util::item;
+
+    fn entry(title: &str, day: u32, read: bool) -> FeedItem {
+        FeedItem {
+            published: Some(format!("2024-01-{day:02}T00:00:00+00:00")),
+            read,
+            ..item(title, &format!("https://example.com/{title}"))
+        }
+    }
+
+    fn sample_app() -> App {
+        let mut db = FeedDb::default();
+        let feeds = [
+            ("Example News", vec![entry("Second", 2, false), entry("First", 1, true)]),
+            ("Quiet Blog", Vec::new()),
+        ];
+        for (index, (title, items)) in feeds.into_iter().enumerate() {
+            let meta = FeedMeta {
+                title: Some(title.to_string()),
+                ..FeedMeta::default()
+            };
+            let url = format!("https://example.com/{index}.xml");
+            db.upsert_feed(url, meta, items, false, true);
+        }
+        db.feeds[0].items[0].author = Some("Ada".to_string());
+        db.feeds[1].last_error = Some("connection refused".to_string());
+        App::loaded(db)
+    }
+
+    /// The screen as text, one line per row with trailing blanks removed.
+    fn snapshot(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 8)).unwrap();
+        terminal.draw(|frame| draw_simple_ui(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                let row: String =
+                    (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect();
+                row.trim_end().to_string()
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    #[test]
+    fn feed_headers_are_followed_by_their_entries() {
+        let mut app = sample_app();
+        let expected = "\
+rss-cli simple view, 1 feeds with errors
+> Feed 1 of 2: Example News, 2 entries, 1 unread
+    Entry 1 of 2: Second by Ada, unread, published 2024-01-02T00:00:00+00:00
+    Entry 2 of 2: First, published 2024-01-01T00:00:00+00:00
+  Feed 2 of 2: Quiet Blog, 0 entries, 0 unread, last refresh failed
+
+Feed 1 of 2: Example News, 2 entries, 1 unread
+";
+        assert_eq!(snapshot(&mut app), expected);
+    }
+
+    #[test]
+    fn moving_down_selects_and_announces_the_entry() {
+        let mut app = sample_app();
+        move_row(&mut app, 1);
+        let expected = "\
+rss-cli simple view, 1 feeds with errors
+  Feed 1 of 2: Example News, 2 entries, 1 unread
+>   Entry 1 of 2: Second by Ada, unread, published 2024-01-02T00:00:00+00:00
+    Entry 2 of 2: First, published 2024-01-01T00:00:00+00:00
+  Feed 2 of 2: Quiet Blog, 0 entries, 0 unread, last refresh failed
+
+Entry 1 of 2: Second by Ada, unread, published 2024-01-02T00:00:00+00:00
+";
+        assert_eq!(snapshot(&mut app), expected);
+        assert!(matches!(app.focus, Focus::Items));
+        assert_eq!(
+            describe_selection(&app, true),
+            "Entry 1 of 2: Second by Ada, unread, published 2024-01-02T00:00:00+00:00, \
+             link https://example.com/Second."
+        );
+    }
+
+    #[test]
+    fn state_is_spelled_out_in_the_header() {
+        let mut app = sample_app();
+        app.read_only = true;
+        app.toggle_unread_only();
+        move_row(&mut app, 2);
+        let expected = "\
+rss-cli simple view, read-only, unread entries only, 1 feeds with errors
+  Feed 1 of 2: Example News, 2 entries, 1 unread
+    Entry 1 of 1: Second by Ada, unread, published 2024-01-02T00:00:00+00:00
+> Feed 2 of 2: Quiet Blog, 0 entries, 0 unread, last refresh failed
+
+
+Feed 2 of 2: Quiet Blog, 0 entries, 0 unread, last refresh failed
+";
+        assert_eq!(snapshot(&mut app), expected);
+    }
+
+    #[test]
+    fn an_empty_database_explains_what_to_do() {
+        let mut app = App::loaded(FeedDb::default());
+        let expected = "\
+rss-cli simple view
+
+
+
+
+
+No feeds. Press a to add one, q to quit.
+";
+        assert_eq!(snapshot(&mut app), expected);
+    }
+}
@@ -1,10 +1,26 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Instant;
 
-use anyhow::Result;
-use chrono::{DateTime, FixedOffset};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
 use ratatui::widgets::ListState;
+use tokio::runtime::Runtime;
+use tokio::task::AbortHandle;
 
-use crate::db::{load_db, save_db, FeedDb, FeedItem, FeedRecord};
+use crate::db::{
+    load_db, save_db, DbError, FeedDb, FeedItem, FeedRecord, DEFAULT_METADATA_INTERVAL_DAYS,
+};
+use crate::feed::{
+    CacheValidators, FeedMeta, FetchError, FetchOptions, FetchedFeed, DEFAULT_JOBS,
+};
+use crate::format::parse_published;
+use crate::opml::{import_opml, ImportSummary};
+
+use super::theme::Theme;
 
 #[derive(Clone, Copy, PartialEq)]
 pub(crate) enum Focus {
@@ -14,51 +30,332 @@ pub(crate) enum Focus {
 
 #[derive(Clone, Copy)]
 pub(crate) enum Mode {
+    /// The database is still being read in the background.
+    Loading,
+    /// Reading the database failed; the error is in `status`.
+    LoadFailed,
     Normal,
     AddUrl,
+    FeedInfo,
+    ShowStatus,
+    ConfirmDuplicate,
+    /// Quit was asked for while a background fetch is still running.
+    ConfirmQuit,
+    AddPreview,
+    FeedSettings,
+    SetPriority,
+    ImportPath,
+    ExportPath,
+    /// Full-screen view of the entry in `App::reading`.
+    Reading,
+}
+
+/// Where the TUI starts once the database is loaded, from the command line.
+#[derive(Default)]
+pub(crate) struct StartView {
+    /// Select this feed, matched like duplicate subscriptions are, and focus
+    /// its newest entry.
+    pub(crate) feed: Option<String>,
+}
+
+/// Drawn next to the status of a refresh that is still running.
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// A refresh that finished in the background, waiting for the event loop to
+/// store it.
+pub(crate) enum RefreshDone {
+    Feed {
+        url: String,
+        force: bool,
+        result: Result<FetchedFeed, FetchError>,
+    },
+    All {
+        results: Vec<(String, Result<FetchedFeed, FetchError>)>,
+        /// Feeds left out because they were rate limited.
+        skipped: usize,
+    },
+    /// A feed being added, to be shown in [`Mode::AddPreview`].
+    Add {
+        url: String,
+        result: Result<(FeedMeta, Vec<FeedItem>), FetchError>,
+    },
+}
+
+/// A fetched feed waiting for the user to confirm the subscription.
+pub(crate) struct AddPreview {
+    pub(crate) url: String,
+    pub(crate) meta: FeedMeta,
+    pub(crate) items: Vec<FeedItem>,
+}
+
+/// A URL entered in the add prompt that matches a feed already subscribed.
+pub(crate) struct PendingAdd {
+    pub(crate) url: String,
+    pub(crate) existing_url: String,
 }
 
 pub(crate) struct App {
     pub(crate) db_path: PathBuf,
     pub(crate) db: FeedDb,
+    /// Set while the database is being read in the background.
+    pub(crate) loading: Option<Receiver<Result<FeedDb, DbError>>>,
+    start: StartView,
+    pub(crate) theme: Theme,
     pub(crate) feed_state: ListState,
     pub(crate) item_state: ListState,
     pub(crate) focus: Focus,
     pub(crate) mode: Mode,
     pub(crate) input: String,
     pub(crate) status: String,
+    pub(crate) status_scroll: u16,
+    /// The entry open in the reading view. It is kept by identity because
+    /// marking it read can drop it from the list under the unread filter.
+    pub(crate) reading: Option<ItemRef>,
+    pub(crate) reading_scroll: u16,
+    /// Text lines the reading view showed when last drawn, for paging.
+    pub(crate) reading_page: u16,
+    pub(crate) suspend_requested: bool,
+    pub(crate) dragging_feed: Option<usize>,
+    pub(crate) pending_add: Option<PendingAdd>,
+    pub(crate) add_preview: Option<AddPreview>,
+    /// First column of the entries pane as last drawn; left of it is the feeds pane.
+    pub(crate) items_panel_x: u16,
+    /// Set when the db path cannot be written; changes stay in memory only.
+    pub(crate) read_only: bool,
+    /// Entries marked for a bulk action, by index in the current view.
+    pub(crate) selected_items: HashSet<usize>,
+    /// Draw the linear, text-only view from `tui --simple`.
+    pub(crate) simple: bool,
+    /// Scroll position of the simple view's list.
+    pub(crate) simple_list: ListState,
+    /// Last selected entry per feed URL; `None` is the All view.
+    pub(crate) selection_memory: HashMap<Option<String>, RememberedItem>,
+    /// Hide read entries from the entries pane (`u`).
+    pub(crate) unread_only: bool,
+    /// Failures from the last refresh of all feeds, listed in the status popup.
+    pub(crate) refresh_errors: Vec<String>,
+    /// How long a stored feed's title is kept before a refresh may replace it;
+    /// a forced refresh always does.
+    pub(crate) metadata_interval: Duration,
+    /// How many feeds a refresh of all feeds fetches at once.
+    pub(crate) jobs: usize,
+    /// Timeout and retries for every fetch the TUI makes.
+    pub(crate) fetch_options: FetchOptions,
+    /// Drives feed fetches, which are async.
+    pub(crate) runtime: Runtime,
+    /// Background refreshes report here when they finish.
+    pub(crate) refresh_sender: Sender<RefreshDone>,
+    pub(crate) refresh_results: Receiver<RefreshDone>,
+    /// What is being fetched in the background, as shown in the status line,
+    /// and since when.
+    pub(crate) refreshing: Option<(String, Instant)>,
+    /// Stops the running background fetch, dropping its result.
+    pub(crate) refresh_task: Option<AbortHandle>,
+    /// Quit once the running background fetch has been stored.
+    pub(crate) quit_when_refreshed: bool,
+}
+
+#[derive(Clone)]
+pub(crate) struct RememberedItem {
+    pub(crate) item: ItemRef,
+    pub(crate) index: usize,
+    pub(crate) published_key: Option<DateTime<FixedOffset>>,
+}
+
+/// Stable identity of an item, independent of where it sits in a list.
+#[derive(Clone, PartialEq)]
+pub(crate) struct ItemRef {
+    pub(crate) feed_url: String,
+    pub(crate) item_key: String,
+}
+
+impl ItemRef {
+    pub(crate) fn new(feed: &FeedRecord, item: &FeedItem) -> Self {
+        Self {
+            feed_url: feed.url.clone(),
+            item_key: item.key(),
+        }
+    }
 }
 
 pub(crate) struct DisplayItem {
+    pub(crate) source: ItemRef,
     pub(crate) title: String,
     pub(crate) feed_title: String,
     pub(crate) published: Option<String>,
     pub(crate) published_key: Option<DateTime<FixedOffset>>,
+    /// Dated after `now`; the date is shown but not used for sorting.
+    pub(crate) future: bool,
     pub(crate) link: Option<String>,
+    pub(crate) author: Option<String>,
+    pub(crate) summary: Option<String>,
+    pub(crate) read: bool,
+}
+
+impl DisplayItem {
+    fn new(feed: &FeedRecord, item: &FeedItem, now: DateTime<Utc>) -> Self {
+        let mut published_key = parse_published(item.published.as_deref());
+        let future = published_key.is_some_and(|key| key > now);
+        if future && feed.clamp_future_dates {
+            // Without a first-seen time there is nothing sane to clamp to, so
+            // a future date sorts like a missing one rather than pinning the top.
+            published_key = None;
+        }
+        Self {
+            source: ItemRef::new(feed, item),
+            title: item.title.clone(),
+            feed_title: feed.title.as_deref().unwrap_or("Untitled").to_string(),
+            published: item.published.clone(),
+            published_key,
+            future,
+            link: item.link.clone(),
+            author: item.author.clone(),
+            summary: item.summary.clone(),
+            read: item.read,
+        }
+    }
 }
 
 pub(crate) const PAGE_JUMP: isize = 5;
 
 impl App {
-    pub(crate) fn new(db_path: PathBuf) -> Result<Self> {
-        let db = if db_path.exists() {
-            load_db(&db_path)?
-        } else {
-            FeedDb::default()
-        };
+    /// Starts with an empty database and reads the real one in the background;
+    /// `poll_load` swaps it in once it is ready and then applies `start`.
+    pub(crate) fn new(
+        db_path: PathBuf,
+        theme: Theme,
+        read_only: bool,
+        start: StartView,
+    ) -> Result<Self> {
         let mut feed_state = ListState::default();
         feed_state.select(Some(0));
         let item_state = ListState::default();
-        Ok(Self {
+        let (refresh_sender, refresh_results) = mpsc::channel();
+        let mut app = Self {
             db_path,
-            db,
+            db: FeedDb::default(),
+            loading: None,
+            start,
+            theme,
             feed_state,
             item_state,
             focus: Focus::Feeds,
-            mode: Mode::Normal,
+            mode: Mode::Loading,
             input: String::new(),
-            status: String::new(),
-        })
+            status: if read_only {
+                "Database is read-only; changes will not be saved.".to_string()
+            } else {
+                String::new()
+            },
+            status_scroll: 0,
+            reading: None,
+            reading_scroll: 0,
+            reading_page: 0,
+            suspend_requested: false,
+            dragging_feed: None,
+            pending_add: None,
+            add_preview: None,
+            items_panel_x: 0,
+            read_only,
+            selected_items: HashSet::new(),
+            simple: false,
+            simple_list: ListState::default(),
+            selection_memory: HashMap::new(),
+            unread_only: false,
+            refresh_errors: Vec::new(),
+            metadata_interval: Duration::days(i64::from(DEFAULT_METADATA_INTERVAL_DAYS)),
+            jobs: DEFAULT_JOBS as usize,
+            fetch_options: FetchOptions::default(),
+            runtime: Runtime::new().context("failed to start the async runtime")?,
+            refresh_sender,
+            refresh_results,
+            refreshing: None,
+            refresh_task: None,
+            quit_when_refreshed: false,
+        };
+        app.start_load();
+        Ok(app)
+    }
+
+    /// Reads the database on a worker thread so the first frame does not wait
+    /// for a large file.
+    pub(crate) fn start_load(&mut self) {
+        self.start_load_with(|path| {
+            if path.exists() {
+                load_db(path)
+            } else {
+                Ok(FeedDb::default())
+            }
+        });
+    }
+
+    /// Starts a background load that reads the database with `load`.
+    fn start_load_with<F>(&mut self, load: F)
+    where
+        F: FnOnce(&Path) -> Result<FeedDb, DbError> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let path = self.db_path.clone();
+        thread::spawn(move || {
+            // The receiver is gone if the TUI quit before the load finished.
+            let _ = sender.send(load(&path));
+        });
+        self.loading = Some(receiver);
+        self.mode = Mode::Loading;
+    }
+
+    /// Swaps in the database once the background load has finished. Returns
+    /// true when the screen needs redrawing, or an error when the start view
+    /// asks for something the database does not have.
+    pub(crate) fn poll_load(&mut self) -> Result<bool> {
+        let Some(receiver) = &self.loading else {
+            return Ok(false);
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result.map_err(anyhow::Error::from),
+            Err(TryRecvError::Empty) => return Ok(false),
+            Err(TryRecvError::Disconnected) => Err(anyhow::anyhow!("the database loader stopped")),
+        };
+        self.loading = None;
+        match result {
+            Ok(db) => {
+                self.db = db;
+                self.feed_state.select(Some(0));
+                self.item_state.select(None);
+                self.mode = Mode::Normal;
+                self.apply_start_view()?;
+            }
+            Err(err) => {
+                self.status = format!("{err:#}");
+                self.mode = Mode::LoadFailed;
+            }
+        }
+        Ok(true)
+    }
+
+    fn apply_start_view(&mut self) -> Result<()> {
+        let Some(url) = self.start.feed.take() else {
+            return Ok(());
+        };
+        let index = self.db.find_feed(&url)?;
+        self.select_feed(index + 1);
+        self.focus = Focus::Items;
+        let newest = self
+            .current_items()
+            .iter()
+            .enumerate()
+            .max_by_key(|(position, item)| (item.published_key, std::cmp::Reverse(*position)))
+            .map(|(position, _)| position);
+        self.item_state.select(newest);
+        Ok(())
+    }
+
+    /// Writes the db back to disk unless the TUI was started read-only.
+    pub(crate) fn save(&self) -> Result<()> {
+        if !self.read_only {
+            save_db(&self.db_path, &self.db)?;
+        }
+        Ok(())
     }
 
     pub(crate) fn selected_feed(&self) -> Option<&FeedRecord> {
@@ -74,13 +371,79 @@ impl App {
         }
     }
 
+    /// Moves the selection in whichever pane contains `column`, leaving focus alone.
+    pub(crate) fn scroll_panel_at(&mut self, column: u16, delta: isize) {
+        if column < self.items_panel_x {
+            self.move_feed(delta);
+        } else {
+            self.move_item(delta);
+        }
+    }
+
     pub(crate) fn move_feed(&mut self, delta: isize) {
         let count = self.db.feeds.len() + 1;
         let current = self.feed_state.selected().unwrap_or(0);
         let next = clamp_index(current as isize + delta, count);
-        self.feed_state.select(Some(next));
-        let items_len = self.current_items_count();
-        self.ensure_item_selection(items_len);
+        self.select_feed(next);
+    }
+
+    /// Selects a row of the feeds list, remembering the entry selected in the
+    /// feed being left and restoring the one remembered for the new feed.
+    pub(crate) fn select_feed(&mut self, index: usize) {
+        if self.feed_state.selected() == Some(index) {
+            return;
+        }
+        self.remember_selection();
+        self.selected_items.clear();
+        self.feed_state.select(Some(index));
+        self.restore_selection();
+    }
+
+    fn remember_selection(&mut self) {
+        let Some(index) = self.item_state.selected() else {
+            return;
+        };
+        let key = self.selected_feed().map(|feed| feed.url.clone());
+        if let Some(item) = self.current_items().into_iter().nth(index) {
+            let remembered = RememberedItem {
+                item: item.source,
+                index,
+                published_key: item.published_key,
+            };
+            self.selection_memory.insert(key, remembered);
+        }
+    }
+
+    /// Restores the remembered entry for the selected feed. When it has gone,
+    /// the entry nearest to it by date is chosen, then the old position.
+    pub(crate) fn restore_selection(&mut self) {
+        let key = self.selected_feed().map(|feed| feed.url.clone());
+        let Some(remembered) = self.selection_memory.get(&key).cloned() else {
+            let items_len = self.current_items_count();
+            self.ensure_item_selection(items_len);
+            return;
+        };
+        if self.select_item_ref(&remembered.item) {
+            return;
+        }
+        let items = self.current_items();
+        if items.is_empty() {
+            self.item_state.select(None);
+            return;
+        }
+        let nearest = remembered.published_key.and_then(|target| {
+            items
+                .iter()
+                .enumerate()
+                .filter_map(|(index, item)| {
+                    item.published_key
+                        .map(|key| (index, (key - target).num_seconds().abs()))
+                })
+                .min_by_key(|(_, distance)| *distance)
+                .map(|(index, _)| index)
+        });
+        let index = nearest.unwrap_or(remembered.index).min(items.len() - 1);
+        self.item_state.select(Some(index));
     }
 
     pub(crate) fn move_item(&mut self, delta: isize) {
@@ -94,29 +457,159 @@ impl App {
         self.item_state.select(Some(next));
     }
 
-    pub(crate) fn upsert_feed(
+    /// Subscribes to the feeds in an OPML file without fetching them.
+    pub(crate) fn import_opml_file(&mut self, path: &Path) -> Result<ImportSummary> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let backfill = self.db.backfill;
+        let summary = import_opml(&mut self.db, &content, backfill)?;
+        self.save()?;
+        Ok(summary)
+    }
+
+    /// Finds the subscribed feed whose URL normalizes to the same as `url`.
+    pub(crate) fn find_duplicate(&self, url: &str) -> Option<usize> {
+        self.db.find_duplicate(url)
+    }
+
+    /// Moves the selected feed up or down the list, keeping it selected and
+    /// marked as being dragged until another key is pressed.
+    pub(crate) fn move_selected_feed(&mut self, delta: isize) -> Result<bool> {
+        let Some(index) = self.feed_state.selected().and_then(|idx| idx.checked_sub(1)) else {
+            return Ok(false);
+        };
+        let next = clamp_index(index as isize + delta, self.db.feeds.len());
+        self.dragging_feed = Some(next);
+        if next == index {
+            return Ok(true);
+        }
+        let feed = self.db.feeds.remove(index);
+        self.db.feeds.insert(next, feed);
+        self.feed_state.select(Some(next + 1));
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Subscribes to a feed that is not stored yet under the database's
+    /// backfill policy and selects it. Returns how many entries were skipped.
+    pub(crate) fn add_feed(
         &mut self,
         url: String,
-        title: Option<String>,
+        meta: FeedMeta,
         items: Vec<FeedItem>,
-    ) -> Result<()> {
-        if let Some(existing) = self.db.feeds.iter_mut().find(|feed| feed.url == url) {
-            existing.title = title;
-            existing.items = items;
-        } else {
-            self.db.feeds.push(FeedRecord {
-                title,
-                url: url.clone(),
-                items,
-            });
+    ) -> Result<usize> {
+        let backfill = self.db.backfill;
+        let (index, skipped) = self.db.add_feed(url, meta, items, backfill);
+        self.selected_items.clear();
+        self.select_feed(index + 1);
+        self.save()?;
+        Ok(skipped)
+    }
+
+    /// Flips the selected feed's preserve_order setting, keeping the selected
+    /// entry selected. Returns the new value, or `None` without a feed.
+    pub(crate) fn toggle_preserve_order(&mut self) -> Result<Option<bool>> {
+        let Some(index) = self.feed_state.selected().and_then(|idx| idx.checked_sub(1)) else {
+            return Ok(None);
+        };
+        let previous = self.selected_item_ref();
+        let Some(feed) = self.db.feeds.get_mut(index) else {
+            return Ok(None);
+        };
+        feed.preserve_order = !feed.preserve_order;
+        let preserve_order = feed.preserve_order;
+        self.selected_items.clear();
+        if let Some(item) = previous {
+            self.select_item_ref(&item);
         }
-        if let Some(index) = self.db.feeds.iter().position(|feed| feed.url == url) {
-            self.feed_state.select(Some(index + 1));
-            let items_len = self.db.feeds[index].items.len();
-            self.ensure_item_selection(items_len);
+        self.save()?;
+        Ok(Some(preserve_order))
+    }
+
+    /// Flips whether future-dated entries of the selected feed are kept out
+    /// of the date sort. Returns the new value, or `None` without a feed.
+    pub(crate) fn toggle_clamp_future_dates(&mut self) -> Result<Option<bool>> {
+        let Some(index) = self.feed_state.selected().and_then(|idx| idx.checked_sub(1)) else {
+            return Ok(None);
+        };
+        let Some(feed) = self.db.feeds.get_mut(index) else {
+            return Ok(None);
+        };
+        feed.clamp_future_dates = !feed.clamp_future_dates;
+        let clamp = feed.clamp_future_dates;
+        self.selected_items.clear();
+        self.save()?;
+        Ok(Some(clamp))
+    }
+
+    /// Sets the selected feed's refresh priority, returning false without a feed.
+    pub(crate) fn set_selected_priority(&mut self, priority: u8) -> Result<bool> {
+        let Some(index) = self.feed_state.selected().and_then(|idx| idx.checked_sub(1)) else {
+            return Ok(false);
+        };
+        let Some(feed) = self.db.feeds.get_mut(index) else {
+            return Ok(false);
+        };
+        feed.priority = priority;
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Stores a fetched feed without moving the feed or entry selection, since
+    /// the user may have moved on while it was fetched. `force` replaces the
+    /// stored entries and title, see [`FeedDb::upsert_feed`]. Like the
+    /// `record_*` methods it leaves saving to the caller, so a batch is
+    /// written once.
+    pub(crate) fn store_refreshed_feed(
+        &mut self,
+        url: String,
+        meta: FeedMeta,
+        items: Vec<FeedItem>,
+        force: bool,
+    ) {
+        let update_metadata =
+            force || self.db.metadata_due(&url, self.metadata_interval, Utc::now());
+        self.db.upsert_feed(url, meta, items, force, update_metadata);
+    }
+
+    /// The status line of a running background refresh, with a spinner that
+    /// turns on every redraw tick.
+    pub(crate) fn refresh_indicator(&self) -> Option<String> {
+        let (label, started) = self.refreshing.as_ref()?;
+        let frame = (started.elapsed().as_millis() / 250) as usize % SPINNER.len();
+        let quitting = if self.quit_when_refreshed { " (quitting when done)" } else { "" };
+        Some(format!("{} {label}\u{2026}{quitting}", SPINNER[frame]))
+    }
+
+    /// Whether a quit that waited for the background fetch can go ahead.
+    pub(crate) fn ready_to_quit(&self) -> bool {
+        self.quit_when_refreshed && self.refreshing.is_none()
+    }
+
+    /// Selects `previous` again after the current view changed underneath it,
+    /// or keeps the entry selection in range when it is gone. Bulk marks are
+    /// dropped since their indices no longer line up.
+    pub(crate) fn reselect_item(&mut self, previous: Option<ItemRef>) {
+        self.selected_items.clear();
+        if !previous.is_some_and(|item| self.select_item_ref(&item)) {
+            let len = self.current_items_count();
+            self.ensure_item_selection(len);
+        }
+    }
+
+    pub(crate) fn record_feed_error(&mut self, url: &str, error: String, http_status: Option<u16>) {
+        self.db.record_fetch_error(url, error, http_status);
+    }
+
+    pub(crate) fn record_not_modified(&mut self, url: &str, validators: CacheValidators) {
+        self.db.record_not_modified(url, validators);
+    }
+
+    /// Stores when the server allows the feed to be fetched again.
+    pub(crate) fn record_rate_limit(&mut self, url: &str, until: DateTime<Utc>) {
+        if let Some(feed) = self.db.feeds.iter_mut().find(|feed| feed.url == url) {
+            feed.rate_limited_until = Some(until.to_rfc3339());
         }
-        save_db(&self.db_path, &self.db)?;
-        Ok(())
     }
 
     pub(crate) fn is_all_selected(&self) -> bool {
@@ -124,47 +617,171 @@ impl App {
     }
 
     pub(crate) fn current_items(&self) -> Vec<DisplayItem> {
+        let now = Utc::now();
         if let Some(feed) = self.selected_feed() {
-            return feed
-                .items
-                .iter()
-                .map(|item| DisplayItem {
-                    title: item.title.clone(),
-                    feed_title: feed.title.as_deref().unwrap_or("Untitled").to_string(),
-                    published: item.published.clone(),
-                    published_key: parse_published(item.published.as_deref()),
-                    link: item.link.clone(),
-                })
-                .collect();
+            return self.feed_view_items(feed, now);
         }
 
         let mut items: Vec<DisplayItem> = self
             .db
             .feeds
             .iter()
-            .flat_map(|feed| {
-                let feed_title = feed.title.as_deref().unwrap_or("Untitled").to_string();
-                feed.items.iter().map(move |item| DisplayItem {
-                    title: item.title.clone(),
-                    feed_title: feed_title.clone(),
-                    published: item.published.clone(),
-                    published_key: parse_published(item.published.as_deref()),
-                    link: item.link.clone(),
-                })
-            })
+            .flat_map(|feed| feed.items.iter().map(move |item| DisplayItem::new(feed, item, now)))
+            .filter(|item| !(self.unread_only && item.read))
             .collect();
         items.sort_by(compare_published_desc);
+        // The sort is stable, so each group keeps its date order.
+        items.sort_by_key(|item| item.read);
+        items
+    }
+
+    /// One feed's entries as its view shows them, honouring the unread filter.
+    pub(crate) fn feed_view_items(
+        &self,
+        feed: &FeedRecord,
+        now: DateTime<Utc>,
+    ) -> Vec<DisplayItem> {
+        let mut items = feed_display_items(feed, now);
+        if self.unread_only {
+            items.retain(|item| !item.read);
+        }
         items
     }
 
+    /// Sets the read flag of the entry `target` refers to, returning false
+    /// when it is gone.
+    pub(crate) fn set_read(&mut self, target: &ItemRef, read: bool) -> Result<bool> {
+        let Some(item) = self.resolve_mut(target) else {
+            return Ok(false);
+        };
+        if item.read != read {
+            item.read = read;
+            // Under the unread filter the entry may just have left the view.
+            let len = self.current_items_count();
+            self.ensure_item_selection(len);
+            self.save()?;
+        }
+        Ok(true)
+    }
+
+    /// Flips the selected entry between read and unread, returning its new
+    /// state, or `None` without a selected entry.
+    pub(crate) fn toggle_selected_read(&mut self) -> Result<Option<bool>> {
+        let Some(item) = self.selected_item() else {
+            return Ok(None);
+        };
+        let read = !item.read;
+        Ok(self.set_read(&item.source, read)?.then_some(read))
+    }
+
+    /// Marks every entry of the selected feed read, or of every feed when
+    /// "All" is selected, returning how many changed.
+    pub(crate) fn mark_feed_read(&mut self) -> Result<usize> {
+        let selected = self.feed_state.selected().unwrap_or(0);
+        let mut changed = 0;
+        for (index, feed) in self.db.feeds.iter_mut().enumerate() {
+            if selected != 0 && selected != index + 1 {
+                continue;
+            }
+            for item in feed.items.iter_mut().filter(|item| !item.read) {
+                item.read = true;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            let len = self.current_items_count();
+            self.ensure_item_selection(len);
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    /// Shows only unread entries, or everything again, keeping the selected
+    /// entry selected while it is still listed.
+    pub(crate) fn toggle_unread_only(&mut self) {
+        let previous = self.selected_item_ref();
+        self.unread_only = !self.unread_only;
+        self.reselect_item(previous);
+    }
+
+    pub(crate) fn selected_item(&self) -> Option<DisplayItem> {
+        let index = self.item_state.selected()?;
+        self.current_items().into_iter().nth(index)
+    }
+
+    /// The entry open in the reading view, or `None` once it is no longer
+    /// stored, for example after a refresh pruned it.
+    pub(crate) fn reading_item(&self) -> Option<DisplayItem> {
+        let (feed, item) = self.resolve(self.reading.as_ref()?)?;
+        Some(DisplayItem::new(feed, item, Utc::now()))
+    }
+
+    /// The stored feed and entry `target` refers to, if both still exist.
+    pub(crate) fn resolve(&self, target: &ItemRef) -> Option<(&FeedRecord, &FeedItem)> {
+        let feed = self.db.feeds.iter().find(|feed| feed.url == target.feed_url)?;
+        let item = feed.items.iter().find(|item| item.key() == target.item_key)?;
+        Some((feed, item))
+    }
+
+    /// The stored entry `target` refers to, for changing it in place.
+    pub(crate) fn resolve_mut(&mut self, target: &ItemRef) -> Option<&mut FeedItem> {
+        let feed = self.db.feeds.iter_mut().find(|feed| feed.url == target.feed_url)?;
+        feed.items.iter_mut().find(|item| item.key() == target.item_key)
+    }
+
+    pub(crate) fn selected_item_ref(&self) -> Option<ItemRef> {
+        self.selected_item().map(|item| item.source)
+    }
+
+    /// Selects the item with the given identity in the current view, returning
+    /// false when it is no longer present.
+    pub(crate) fn select_item_ref(&mut self, target: &ItemRef) -> bool {
+        match self.current_items().iter().position(|item| &item.source == target) {
+            Some(index) => {
+                self.item_state.select(Some(index));
+                true
+            }
+            None => false,
+        }
+    }
+
     pub(crate) fn current_items_count(&self) -> usize {
+        let count = |feed: &FeedRecord| {
+            if self.unread_only {
+                feed.unread_count()
+            } else {
+                feed.items.len()
+            }
+        };
         if let Some(feed) = self.selected_feed() {
-            feed.items.len()
+            count(feed)
         } else {
-            self.db.feeds.iter().map(|feed| feed.items.len()).sum()
+            self.db.feeds.iter().map(count).sum()
         }
     }
 
+    /// Clamps both list selections and scroll offsets to the current data so
+    /// the stateful widgets never point past the end of a list. Called once per
+    /// loop iteration, after any mutation and before drawing.
+    pub(crate) fn reconcile_selection(&mut self) {
+        let feeds_len = self.db.feeds.len() + 1;
+        let feed = self.feed_state.selected().unwrap_or(0).min(feeds_len - 1);
+        self.feed_state.select(Some(feed));
+        clamp_offset(&mut self.feed_state, feeds_len);
+
+        let items_len = self.current_items_count();
+        self.ensure_item_selection(items_len);
+        clamp_offset(&mut self.item_state, items_len);
+        self.selected_items.retain(|&index| index < items_len);
+    }
+
+    /// Marks every entry in the current view, returning how many there are.
+    pub(crate) fn select_all_items(&mut self) -> usize {
+        let count = self.current_items_count();
+        self.selected_items = (0..count).collect();
+        count
+    }
+
     pub(crate) fn ensure_item_selection(&mut self, len: usize) {
         if len == 0 {
             self.item_state.select(None);
@@ -176,6 +793,16 @@ impl App {
     }
 }
 
+/// The entries of one feed as the feed's own view shows them.
+pub(crate) fn feed_display_items(feed: &FeedRecord, now: DateTime<Utc>) -> Vec<DisplayItem> {
+    let mut items: Vec<DisplayItem> =
+        feed.items.iter().map(|item| DisplayItem::new(feed, item, now)).collect();
+    if !feed.preserve_order {
+        items.sort_by(compare_published_desc);
+    }
+    items
+}
+
 fn clamp_index(index: isize, len: usize) -> usize {
     let last = len.saturating_sub(1) as isize;
     if index < 0 {
@@ -187,8 +814,11 @@ fn clamp_index(index: isize, len: usize) -> usize {
     }
 }
 
-fn parse_published(value: Option<&str>) -> Option<DateTime<FixedOffset>> {
-    value.and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+fn clamp_offset(state: &mut ListState, len: usize) {
+    let selected = state.selected().unwrap_or(0);
+    if state.offset() >= len || state.offset() > selected {
+        *state.offset_mut() = selected;
+    }
 }
 
 fn compare_published_desc(a: &DisplayItem, b: &DisplayItem) -> std::cmp::Ordering {
@@ -199,3 +829,420 @@ fn compare_published_desc(a: &DisplayItem, b: &DisplayItem) -> std::cmp::Orderin
         (None, None) => std::cmp::Ordering::Equal,
     }
 }
+
+#[cfg(test)]
+impl App {
+    /// An app that has finished loading and shows `db`, saving to a fresh
+    /// temporary directory.
+    pub(crate) fn loaded(db: FeedDb) -> Self {
+        let path = crate::test_util::temp_dir("tui").join("feeds.json");
+        let mut app = App::new(path, Theme::detect(true), false, StartView::default()).unwrap();
+        while !app.poll_load().unwrap() {
+            thread::yield_now();
+        }
+        app.db = db;
+        app
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use super::*;
+    use crate::test_util::item;
+    use crate::tui::input::handle_key;
+
+    /// A small xorshift generator, enough to vary the mutation sequences.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound.max(1) as u64) as usize
+        }
+    }
+
+    fn items(feed: usize, count: usize) -> Vec<FeedItem> {
+        (0..count)
+            .map(|index| {
+                let link = format!("https://example.com/{feed}/{index}");
+                item(&format!("Entry {index}"), &link)
+            })
+            .collect()
+    }
+
+    fn mutate(app: &mut App, rng: &mut Rng, next_feed: &mut usize) {
+        let feeds = app.db.feeds.len();
+        match rng.below(9) {
+            0 => {
+                let url = format!("https://example.com/{next_feed}.xml");
+                let count = rng.below(6);
+                app.db.upsert_feed(url, FeedMeta::default(), items(*next_feed, count), false, true);
+                *next_feed += 1;
+            }
+            1 if feeds > 0 => {
+                app.db.feeds.remove(rng.below(feeds));
+            }
+            2 if feeds > 0 => {
+                // A refresh that shrinks the feed, dropping missing entries at once.
+                let url = app.db.feeds[rng.below(feeds)].url.clone();
+                let count = rng.below(3);
+                app.store_refreshed_feed(url, FeedMeta::default(), items(0, count), true);
+            }
+            3 => app.toggle_unread_only(),
+            4 => {
+                let _ = app.toggle_selected_read();
+            }
+            5 => app.select_feed(rng.below(feeds + 3)),
+            6 => app.item_state.select(Some(rng.below(20))),
+            7 => *app.feed_state.offset_mut() = rng.below(20),
+            _ => *app.item_state.offset_mut() = rng.below(20),
+        }
+        if rng.below(4) == 0 {
+            app.selected_items.insert(rng.below(20));
+        }
+    }
+
+    fn assert_invariants(app: &App) {
+        let feeds_len = app.db.feeds.len() + 1;
+        let feed = app.feed_state.selected().expect("a feed row is always selected");
+        assert!(feed < feeds_len);
+        assert!(app.feed_state.offset() <= feed);
+
+        let items_len = app.current_items_count();
+        assert_eq!(items_len, app.current_items().len());
+        match app.item_state.selected() {
+            Some(item) => {
+                assert!(item < items_len);
+                assert!(app.item_state.offset() <= item);
+            }
+            None => assert_eq!(items_len, 0),
+        }
+        assert!(app.selected_items.iter().all(|&index| index < items_len));
+    }
+
+    fn at(date: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc)
+    }
+
+    const NOW: &str = "2024-06-01T00:00:00Z";
+
+    /// A feed sorted newest first with an entry dated after `NOW`.
+    fn skewed_feed(clamp_future_dates: bool) -> FeedRecord {
+        let dated = |title: &str, published: Option<&str>| FeedItem {
+            published: published.map(str::to_string),
+            ..item(title, &format!("https://example.com/{title}"))
+        };
+        let items = vec![
+            dated("past", Some("2024-05-01T00:00:00+00:00")),
+            dated("skewed", Some("2038-01-19T03:14:07+00:00")),
+            dated("recent", Some("2024-05-31T00:00:00+00:00")),
+            dated("undated", None),
+        ];
+        let mut db = FeedDb::default();
+        let url = "https://example.com/feed".to_string();
+        db.upsert_feed(url, FeedMeta::default(), items, false, true);
+        let mut feed = db.feeds.remove(0);
+        feed.preserve_order = false;
+        feed.clamp_future_dates = clamp_future_dates;
+        feed
+    }
+
+    #[test]
+    fn clamped_future_entries_sort_like_undated_ones() {
+        let feed = skewed_feed(true);
+        let items = feed_display_items(&feed, at(NOW));
+
+        let titles: Vec<_> = items.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, ["recent", "past", "skewed", "undated"]);
+        assert!(items[2].future);
+        assert_eq!(items[2].published_key, None);
+        assert_eq!(items.iter().filter(|item| item.future).count(), 1);
+    }
+
+    #[test]
+    fn unclamped_future_entries_sort_by_their_date() {
+        let items = feed_display_items(&skewed_feed(false), at(NOW));
+
+        let titles: Vec<_> = items.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, ["skewed", "recent", "past", "undated"]);
+        assert!(items[0].future);
+        assert!(items[0].published_key.is_some());
+    }
+
+    #[test]
+    fn clamping_leaves_stored_dates_alone() {
+        let feed = skewed_feed(true);
+        let items = feed_display_items(&feed, at(NOW));
+
+        assert_eq!(feed.items[1].published.as_deref(), Some("2038-01-19T03:14:07+00:00"));
+        assert_eq!(items[2].published.as_deref(), Some("2038-01-19T03:14:07+00:00"));
+        // Once the clock passes the date, the entry stops being future at all.
+        let later = feed_display_items(&feed, at("2038-02-01T00:00:00Z"));
+        assert_eq!(later[0].title, "skewed");
+        assert!(!later[0].future);
+    }
+
+    fn two_feeds() -> App {
+        let mut db = FeedDb::default();
+        for feed in 0..2 {
+            let url = format!("https://example.com/{feed}.xml");
+            let items = items(feed, 3)
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| FeedItem {
+                    id: Some(format!("{feed}-{index}")),
+                    ..item
+                })
+                .collect();
+            db.upsert_feed(url, FeedMeta::default(), items, false, true);
+        }
+        App::loaded(db)
+    }
+
+    fn reference(app: &App, feed: usize, item: usize) -> ItemRef {
+        ItemRef::new(&app.db.feeds[feed], &app.db.feeds[feed].items[item])
+    }
+
+    #[test]
+    fn resolve_follows_an_entry_whose_link_changed() {
+        let mut app = two_feeds();
+        let target = reference(&app, 1, 2);
+        let url = app.db.feeds[1].url.clone();
+        let mut refreshed = items(1, 3);
+        for (index, item) in refreshed.iter_mut().enumerate() {
+            item.id = Some(format!("1-{index}"));
+            item.link = Some(format!("https://example.com/moved/{index}"));
+        }
+        app.store_refreshed_feed(url, FeedMeta::default(), refreshed, true);
+
+        let (feed, item) = app.resolve(&target).unwrap();
+        assert_eq!(feed.url, "https://example.com/1.xml");
+        assert_eq!(item.link.as_deref(), Some("https://example.com/moved/2"));
+    }
+
+    #[test]
+    fn resolve_follows_set_read_and_feed_moves() {
+        let mut app = two_feeds();
+        let target = reference(&app, 0, 1);
+        assert!(app.set_read(&target, true).unwrap());
+        assert!(app.resolve(&target).unwrap().1.read);
+
+        app.select_feed(1);
+        assert!(app.move_selected_feed(1).unwrap());
+        assert_eq!(app.db.feeds[1].url, target.feed_url);
+        assert!(app.resolve(&target).unwrap().1.read);
+
+        app.resolve_mut(&target).unwrap().read = false;
+        assert!(!app.db.feeds[1].items[1].read);
+    }
+
+    #[test]
+    fn resolve_fails_once_the_entry_or_feed_is_gone() {
+        let mut app = two_feeds();
+        let pruned = reference(&app, 0, 2);
+        let deleted = reference(&app, 1, 0);
+        let url = app.db.feeds[0].url.clone();
+        let kept: Vec<FeedItem> = app.db.feeds[0].items.drain(..2).collect();
+        app.store_refreshed_feed(url, FeedMeta::default(), kept, true);
+        app.db.feeds.remove(1);
+
+        assert!(app.resolve(&pruned).is_none());
+        assert!(app.resolve(&deleted).is_none());
+        assert!(!app.set_read(&pruned, true).unwrap());
+        app.reading = Some(deleted);
+        assert!(app.reading_item().is_none());
+    }
+
+    #[test]
+    fn reading_view_keeps_its_entry_across_a_refresh() {
+        let mut app = two_feeds();
+        app.reading = Some(reference(&app, 0, 0));
+        let url = app.db.feeds[0].url.clone();
+        let mut refreshed = items(0, 1);
+        refreshed[0].id = Some("0-0".to_string());
+        refreshed[0].title = "Entry 0, corrected".to_string();
+        app.store_refreshed_feed(url, FeedMeta::default(), refreshed, true);
+
+        assert_eq!(app.reading_item().unwrap().title, "Entry 0, corrected");
+    }
+
+    #[test]
+    fn start_view_selects_the_feed_and_its_newest_entry() {
+        let mut app = two_feeds();
+        let url = app.db.feeds[1].url.clone();
+        let days = [2, 5, 3].map(|day| FeedItem {
+            id: Some(format!("day-{day}")),
+            published: Some(format!("2024-01-{day:02}T00:00:00+00:00")),
+            ..item(&format!("Day {day}"), &format!("https://example.com/{day}"))
+        });
+        app.store_refreshed_feed(url, FeedMeta::default(), days.into(), true);
+        app.start = StartView {
+            feed: Some("HTTPS://Example.com/1.xml/".to_string()),
+        };
+        app.apply_start_view().unwrap();
+
+        assert_eq!(app.feed_state.selected(), Some(2));
+        assert!(app.focus == Focus::Items);
+        assert_eq!(app.item_state.selected(), Some(1));
+        assert_eq!(selected_key(&app), "day-5");
+    }
+
+    #[test]
+    fn start_view_names_close_matches_for_an_unknown_feed() {
+        let mut app = two_feeds();
+        app.start = StartView {
+            feed: Some("https://example.com/2.xml".to_string()),
+        };
+        let err = app.apply_start_view().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no subscribed feed matches https://example.com/2.xml; \
+             close matches: https://example.com/0.xml, https://example.com/1.xml"
+        );
+
+        app.start = StartView {
+            feed: Some("https://elsewhere.org/feed".to_string()),
+        };
+        let err = app.apply_start_view().unwrap_err();
+        assert_eq!(err.to_string(), "no subscribed feed matches https://elsewhere.org/feed");
+    }
+
+    fn selected_key(app: &App) -> String {
+        app.selected_item().unwrap().source.item_key
+    }
+
+    #[test]
+    fn selection_survives_entries_inserted_above_it() {
+        let mut app = two_feeds();
+        app.select_feed(1);
+        app.item_state.select(Some(2));
+        app.select_feed(2);
+
+        let url = app.db.feeds[0].url.clone();
+        let mut refreshed = items(0, 5);
+        for (index, item) in refreshed.iter_mut().enumerate() {
+            item.id = Some(match index {
+                0 => "0-new".to_string(),
+                1 => "0-newer".to_string(),
+                index => format!("0-{}", index - 2),
+            });
+        }
+        app.store_refreshed_feed(url, FeedMeta::default(), refreshed, false);
+        app.select_feed(1);
+
+        assert_eq!(app.item_state.selected(), Some(4));
+        assert_eq!(selected_key(&app), "0-2");
+    }
+
+    #[test]
+    fn pruned_selection_falls_back_to_the_nearest_date() {
+        let dated = |day: u32| FeedItem {
+            id: Some(format!("day-{day}")),
+            published: Some(format!("2024-01-{day:02}T00:00:00+00:00")),
+            ..item(&format!("Day {day}"), &format!("https://example.com/{day}"))
+        };
+        let mut app = two_feeds();
+        let url = app.db.feeds[0].url.clone();
+        let days = [5, 4, 3, 2, 1].map(dated).into();
+        app.store_refreshed_feed(url.clone(), FeedMeta::default(), days, true);
+        app.select_feed(1);
+        app.item_state.select(Some(2));
+        assert_eq!(selected_key(&app), "day-3");
+        app.select_feed(2);
+
+        let days = [10, 9, 5, 4, 1].map(dated).into();
+        app.store_refreshed_feed(url, FeedMeta::default(), days, true);
+        app.select_feed(1);
+
+        // Day 4 is nearest to day 3; the old position would have given day 5.
+        assert_eq!(selected_key(&app), "day-4");
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> bool {
+        handle_key(app, KeyEvent::from(code)).unwrap()
+    }
+
+    #[test]
+    fn input_stays_responsive_during_a_slow_load() {
+        let mut app = App::loaded(FeedDb::default());
+        let (release, wait) = mpsc::channel::<()>();
+        app.start_load_with(move |_| {
+            // Stands in for a database on a slow network mount.
+            wait.recv().unwrap();
+            let mut db = FeedDb::default();
+            let url = "https://example.com/feed.xml".to_string();
+            db.upsert_feed(url, FeedMeta::default(), Vec::new(), false, true);
+            Ok(db)
+        });
+
+        let started = Instant::now();
+        for code in [KeyCode::Char('a'), KeyCode::Char('r'), KeyCode::Down, KeyCode::Enter] {
+            assert!(!press(&mut app, code));
+            assert!(!app.poll_load().unwrap());
+            assert!(matches!(app.mode, Mode::Loading));
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(app.db.feeds.is_empty());
+
+        release.send(()).unwrap();
+        while !app.poll_load().unwrap() {
+            thread::yield_now();
+        }
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.db.feeds.len(), 1);
+    }
+
+    #[test]
+    fn quit_works_while_loading() {
+        let mut app = App::loaded(FeedDb::default());
+        let (_release, wait) = mpsc::channel::<()>();
+        app.start_load_with(move |_| {
+            let _ = wait.recv();
+            Ok(FeedDb::default())
+        });
+        assert!(press(&mut app, KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn failed_load_offers_retry() {
+        let mut app = App::loaded(FeedDb::default());
+        app.start_load_with(|path| {
+            Err(DbError::Read {
+                path: path.to_path_buf(),
+                source: std::io::Error::other("disk on fire"),
+            })
+        });
+        while !app.poll_load().unwrap() {
+            thread::yield_now();
+        }
+        assert!(matches!(app.mode, Mode::LoadFailed));
+        assert!(app.status.contains("disk on fire"), "{}", app.status);
+
+        // The path does not exist, so the retry loads an empty database.
+        assert!(!press(&mut app, KeyCode::Char('r')));
+        assert!(matches!(app.mode, Mode::Loading));
+        while !app.poll_load().unwrap() {
+            thread::yield_now();
+        }
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn reconcile_selection_holds_under_random_mutations() {
+        for seed in 1..=50u64 {
+            let mut app = App::loaded(FeedDb::default());
+            app.read_only = true;
+            let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let mut next_feed = 0;
+            for _ in 0..200 {
+                mutate(&mut app, &mut rng, &mut next_feed);
+                app.reconcile_selection();
+                assert_invariants(&app);
+            }
+        }
+    }
+}
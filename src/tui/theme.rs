@@ -0,0 +1,125 @@
+use std::env;
+
+use ratatui::prelude::*;
+
+use crate::rollup::{ASCII_SPARK_LEVELS, SPARK_LEVELS};
+
+/// Styles for every piece of information the TUI draws. The mono variant
+/// relies on modifiers only, for `NO_COLOR`, dumb terminals and `--no-color`.
+#[derive(Clone, Copy)]
+pub(crate) struct Theme {
+    color: bool,
+}
+
+impl Theme {
+    pub(crate) fn detect(no_color: bool) -> Self {
+        Self {
+            color: !no_color && color_supported(),
+        }
+    }
+
+    pub(crate) fn border(&self, is_focused: bool) -> Style {
+        match (self.color, is_focused) {
+            (true, true) => Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            (true, false) => Style::default().fg(Color::DarkGray),
+            (false, true) => Style::default().add_modifier(Modifier::BOLD),
+            (false, false) => Style::default().add_modifier(Modifier::DIM),
+        }
+    }
+
+    pub(crate) fn feed_label(&self) -> Style {
+        self.pick(Style::default().fg(Color::Cyan), Modifier::ITALIC)
+    }
+
+    pub(crate) fn published(&self) -> Style {
+        self.pick(Style::default().fg(Color::Yellow), Modifier::DIM)
+    }
+
+    pub(crate) fn link(&self) -> Style {
+        self.pick(Style::default().fg(Color::Blue), Modifier::UNDERLINED)
+    }
+
+    pub(crate) fn warning(&self) -> Style {
+        self.pick(Style::default().fg(Color::Yellow), Modifier::ITALIC)
+    }
+
+    pub(crate) fn error(&self) -> Style {
+        self.pick(Style::default().fg(Color::Red), Modifier::BOLD)
+    }
+
+    pub(crate) fn label(&self) -> Style {
+        self.pick(Style::default().fg(Color::Cyan), Modifier::BOLD)
+    }
+
+    /// Colors a response code by class: 2xx green, 3xx yellow, 4xx red, 5xx orange.
+    pub(crate) fn http_status(&self, status: u16) -> Style {
+        match status {
+            200..=299 => self.pick(Style::default().fg(Color::Green), Modifier::empty()),
+            300..=399 => self.pick(Style::default().fg(Color::Yellow), Modifier::ITALIC),
+            400..=499 => self.pick(Style::default().fg(Color::Red), Modifier::BOLD),
+            500..=599 => self.pick(Style::default().fg(Color::Indexed(208)), Modifier::BOLD),
+            _ => Style::default(),
+        }
+    }
+
+    pub(crate) fn unread(&self) -> Style {
+        Style::default().add_modifier(Modifier::BOLD)
+    }
+
+    pub(crate) fn unread_badge(&self) -> Style {
+        self.pick(Style::default().fg(Color::Yellow), Modifier::BOLD)
+    }
+
+    pub(crate) fn author(&self) -> Style {
+        self.pick(Style::default().fg(Color::Gray), Modifier::DIM)
+    }
+
+    pub(crate) fn summary(&self) -> Style {
+        self.pick(Style::default().fg(Color::Gray), Modifier::DIM)
+    }
+
+    pub(crate) fn read(&self) -> Style {
+        self.pick(Style::default().fg(Color::DarkGray), Modifier::DIM)
+    }
+
+    /// Reversed in both themes, so marked rows stand out even where the
+    /// palette's blue is close to the background; in color, reversing a blue
+    /// foreground still paints the row blue.
+    pub(crate) fn marked(&self) -> Style {
+        self.pick(reversed().fg(Color::Blue), Modifier::REVERSED)
+    }
+
+    /// Reversed in both themes, like [`Theme::marked`].
+    pub(crate) fn dragging(&self) -> Style {
+        self.pick(reversed().fg(Color::DarkGray), Modifier::REVERSED)
+    }
+
+    /// Sparkline glyphs; the mono theme sticks to ASCII, since terminals that
+    /// cannot do color often lack the block characters too.
+    pub(crate) fn spark_levels(&self) -> &'static [char] {
+        if self.color { SPARK_LEVELS } else { ASCII_SPARK_LEVELS }
+    }
+
+    fn pick(&self, color: Style, mono: Modifier) -> Style {
+        if self.color {
+            color
+        } else {
+            Style::default().add_modifier(mono)
+        }
+    }
+}
+
+fn reversed() -> Style {
+    Style::default().add_modifier(Modifier::REVERSED)
+}
+
+fn color_supported() -> bool {
+    if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+    match env::var("TERM") {
+        Ok(term) => !term.is_empty() && term != "dumb",
+        // Windows consoles usually leave TERM unset but handle colors fine.
+        Err(_) => cfg!(windows),
+    }
+}
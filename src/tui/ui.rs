@@ -1,96 +1,710 @@
+use chrono::{DateTime, Utc};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Wrap};
+use ratatui::widgets::{
+    Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+    ScrollbarState, Wrap,
+};
+
+use crate::format::parse_published;
+use crate::rollup::{sparkline, weekly_counts};
 
 use super::state::{App, Focus, Mode};
 
+/// Shown in place of both views until the database has been read.
+pub(super) fn draw_loading(frame: &mut Frame, app: &App) {
+    let area = centered_rect(60, 30, frame.size());
+    let lines = match app.mode {
+        Mode::LoadFailed => vec![
+            Line::styled(
+                format!("Could not load {}", app.db_path.display()),
+                app.theme.error(),
+            ),
+            Line::raw(app.status.clone()),
+            Line::raw(""),
+            Line::raw(mode_prompt(app)),
+        ],
+        _ => vec![Line::raw(format!("Loading {}\u{2026}", app.db_path.display()))],
+    };
+    let text = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().title("rss-cli").borders(Borders::ALL));
+    frame.render_widget(text, area);
+}
+
+/// The entry open in the reading view, full screen and wrapped to its width,
+/// with the status or key hints on the last line.
+pub(super) fn draw_reading(frame: &mut Frame, app: &mut App) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(frame.size());
+
+    let lines = match app.reading_item() {
+        Some(item) => {
+            let mut lines = vec![
+                Line::styled(item.title.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                Line::styled(format!("Feed: {}", item.feed_title), app.theme.feed_label()),
+            ];
+            if let Some(published) = item.published.as_deref().filter(|date| !date.is_empty()) {
+                let future = if item.future { " (future)" } else { "" };
+                lines.push(Line::styled(
+                    format!("Published: {published}{future}"),
+                    app.theme.published(),
+                ));
+            }
+            if let Some(author) = &item.author {
+                lines.push(Line::styled(format!("Author: {author}"), app.theme.author()));
+            }
+            if let Some(link) = item.link.as_deref().filter(|link| !link.is_empty()) {
+                lines.push(Line::styled(format!("Link: {link}"), app.theme.link()));
+            }
+            lines.push(Line::raw(""));
+            match &item.summary {
+                Some(summary) => {
+                    lines.extend(summary.lines().map(|line| Line::raw(line.to_string())));
+                }
+                None => lines.push(Line::styled(
+                    "No summary or content is stored for this entry.",
+                    app.theme.summary(),
+                )),
+            }
+            lines
+        }
+        None => vec![Line::raw("This entry is no longer stored.")],
+    };
+
+    let area = layout[0];
+    let text = Paragraph::new(lines).wrap(Wrap { trim: false });
+    let visible = area.height.saturating_sub(2);
+    let line_count = text.line_count(area.width.saturating_sub(2));
+    let max_scroll = u16::try_from(line_count.saturating_sub(usize::from(visible)))
+        .unwrap_or(u16::MAX);
+    app.reading_scroll = app.reading_scroll.min(max_scroll);
+    app.reading_page = visible;
+
+    let text = text
+        .scroll((app.reading_scroll, 0))
+        .block(Block::default().title("Reading").borders(Borders::ALL));
+    frame.render_widget(text, area);
+    if max_scroll > 0 {
+        let mut scrollbar = ScrollbarState::new(usize::from(max_scroll) + 1)
+            .position(usize::from(app.reading_scroll));
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            area,
+            &mut scrollbar,
+        );
+    }
+
+    let footer = if app.status.is_empty() {
+        mode_prompt(app)
+    } else {
+        app.status.clone()
+    };
+    frame.render_widget(Paragraph::new(footer), layout[1]);
+}
+
+/// Summary lines shown under each entry in the entries pane.
+const SUMMARY_PREVIEW_LINES: usize = 2;
+
 pub(super) fn draw_ui(frame: &mut Frame, app: &mut App) {
     let layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(2), Constraint::Length(2)])
+        .constraints([Constraint::Length(1), Constraint::Min(2), Constraint::Length(2)])
         .split(frame.size());
 
+    let error_count = app.db.feeds.iter().filter(|feed| feed.last_error.is_some()).count();
+    let mut header = vec![Span::raw("rss-cli")];
+    if app.read_only {
+        header.push(Span::raw(" "));
+        header.push(Span::styled("[read-only]", app.theme.warning()));
+    }
+    if error_count > 0 {
+        header.push(Span::raw(" "));
+        header.push(Span::styled(format!("[{error_count} errors]"), app.theme.error()));
+    }
+    let header = Line::from(header);
+    frame.render_widget(Paragraph::new(header), layout[0]);
+
     let main = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
-        .split(layout[0]);
+        .split(layout[1]);
+    app.items_panel_x = main[1].x;
 
     let mut feed_items = Vec::with_capacity(app.db.feeds.len() + 1);
-    feed_items.push(ListItem::new(format!("All\n{} feeds", app.db.feeds.len())));
-    for feed in &app.db.feeds {
+    let total_unread: usize = app.db.feeds.iter().map(|feed| feed.unread_count()).sum();
+    feed_items.push(ListItem::new(vec![
+        Line::from(vec![Span::raw("All "), unread_badge(app, total_unread)]),
+        Line::from(format!("{} feeds", app.db.feeds.len())),
+    ]));
+    for (index, feed) in app.db.feeds.iter().enumerate() {
         let title = feed.title.as_deref().unwrap_or("Untitled");
-        feed_items.push(ListItem::new(format!("{title}\n{}", feed.url)));
+        let title = Line::from(vec![
+            Span::raw(format!("{title} ")),
+            unread_badge(app, feed.unread_count()),
+        ]);
+        let mut lines = vec![title, Line::from(feed.url.clone())];
+        if let Some(warning) = feed.warnings.first() {
+            lines.push(Line::from(format!("! {warning}")).style(app.theme.warning()));
+        }
+        let mut item = ListItem::new(lines);
+        if app.dragging_feed == Some(index) {
+            item = item.style(app.theme.dragging());
+        }
+        feed_items.push(item);
     }
 
     let feeds = List::new(feed_items)
         .block(
             Block::default()
-                .title("Feeds")
+                .title(pane_title("Feeds", app.focus == Focus::Feeds))
                 .borders(Borders::ALL)
-                .border_style(style_for_focus(app.focus == Focus::Feeds)),
+                .border_style(app.theme.border(app.focus == Focus::Feeds)),
         )
         .highlight_style(Style::default().add_modifier(Modifier::BOLD))
         .highlight_symbol(">> ");
     frame.render_stateful_widget(feeds, main[0], &mut app.feed_state);
 
     let entries = app.current_items();
-    app.ensure_item_selection(entries.len());
 
     let entry_items: Vec<ListItem> = entries
         .iter()
-        .map(|item| {
+        .enumerate()
+        .map(|(index, item)| {
             let mut lines = Vec::new();
-            lines.push(Line::from(item.title.clone()).style(Style::default()));
+            let title_style = if item.read {
+                app.theme.read()
+            } else {
+                app.theme.unread()
+            };
+            let mut title = vec![Span::styled(item.title.clone(), title_style)];
+            if let Some(author) = &item.author {
+                title.push(Span::styled(format!(" by {author}"), app.theme.author()));
+            }
+            lines.push(Line::from(title));
             if app.is_all_selected() {
-                lines.push(Line::from(item.feed_title.clone()).style(Style::default().fg(Color::Cyan)));
+                lines.push(Line::from(item.feed_title.clone()).style(app.theme.feed_label()));
             }
-            if let Some(published) = &item.published {
-                if !published.is_empty() {
-                    lines.push(
-                        Line::from(published.clone())
-                            .style(Style::default().fg(Color::Yellow)),
-                    );
-                }
+            if let Some(published) = &item.published
+                && !published.is_empty()
+            {
+                let label = if item.future {
+                    format!("{published} (future)")
+                } else {
+                    published.clone()
+                };
+                lines.push(Line::from(label).style(app.theme.published()));
             }
-            if let Some(link) = &item.link {
-                if !link.is_empty() {
-                    lines.push(Line::from(link.clone()).style(Style::default().fg(Color::Blue)));
+            if let Some(link) = &item.link
+                && !link.is_empty()
+            {
+                lines.push(Line::from(link.clone()).style(app.theme.link()));
+            }
+            if let Some(summary) = &item.summary {
+                let preview = summary.lines().filter(|line| !line.is_empty());
+                for line in preview.take(SUMMARY_PREVIEW_LINES) {
+                    lines.push(Line::from(line.to_string()).style(app.theme.summary()));
                 }
             }
-            ListItem::new(lines)
+            let item = ListItem::new(lines);
+            if app.selected_items.contains(&index) {
+                item.style(app.theme.marked())
+            } else {
+                item
+            }
         })
         .collect();
 
     let entries_list = List::new(entry_items)
         .block(
             Block::default()
-                .title("Entries")
+                .title(pane_title(
+                    if app.unread_only { "Entries [unread]" } else { "Entries" },
+                    app.focus == Focus::Items,
+                ))
                 .borders(Borders::ALL)
-                .border_style(style_for_focus(app.focus == Focus::Items)),
+                .border_style(app.theme.border(app.focus == Focus::Items)),
         )
         .highlight_style(Style::default().add_modifier(Modifier::BOLD))
         .highlight_symbol(">> ");
     frame.render_stateful_widget(entries_list, main[1], &mut app.item_state);
 
     let status_text = match app.mode {
-        Mode::AddUrl => format!("Add feed URL: {} (Enter to save, Esc to cancel)", app.input),
         Mode::Normal => {
-            if app.status.is_empty() {
-                "q quit | a add | r refresh | d delete | left/right switch | arrows move".to_string()
-            } else {
+            if let Some(indicator) = app.refresh_indicator() {
+                indicator
+            } else if !app.status.is_empty() {
                 app.status.clone()
+            } else if let (Focus::Items, Some(selected)) = (app.focus, app.item_state.selected()) {
+                let unread = entries.iter().filter(|item| !item.read).count();
+                format!("Item {} of {} ({unread} unread)", selected + 1, entries.len())
+            } else {
+                "q quit | a add | r refresh | d delete | left/right switch | arrows move".to_string()
             }
         }
+        _ => mode_prompt(app),
+    };
+    let status_text = match app.mode {
+        Mode::Normal if app.unread_only => format!("Unread only | {status_text}"),
+        _ => status_text,
     };
     let status = Paragraph::new(status_text)
         .wrap(Wrap { trim: true })
         .block(Block::default().borders(Borders::TOP));
-    frame.render_widget(status, layout[1]);
+    frame.render_widget(status, layout[2]);
+
+    draw_popup(frame, app);
+}
+
+/// The `(n)` after a feed title, highlighted while anything is unread.
+fn unread_badge(app: &App, unread: usize) -> Span<'static> {
+    let badge = format!("({unread})");
+    if unread > 0 {
+        Span::styled(badge, app.theme.unread_badge())
+    } else {
+        Span::raw(badge)
+    }
+}
+
+/// Status bar text for every mode other than Normal.
+pub(super) fn mode_prompt(app: &App) -> String {
+    match app.mode {
+        Mode::Normal => app.status.clone(),
+        Mode::Loading => "q quit".to_string(),
+        Mode::LoadFailed => "r retry | q quit".to_string(),
+        Mode::AddUrl => format!("Add feed URL: {} (Enter to save, Esc to cancel)", app.input),
+        Mode::FeedInfo => "Press any key to close.".to_string(),
+        Mode::Reading => "j/k scroll | PgUp/PgDn page | o open link | Esc back".to_string(),
+        Mode::ShowStatus => "j/k scroll | any other key closes".to_string(),
+        Mode::ConfirmDuplicate => duplicate_prompt(app),
+        Mode::ConfirmQuit => format!(
+            "{} is still running. Quit anyway (q), wait for it (w), \
+             cancel it and quit (c), or go back (Esc)?",
+            app.refreshing.as_ref().map_or("A fetch", |(label, _)| label.as_str())
+        ),
+        Mode::AddPreview => "Enter to subscribe | Esc to discard".to_string(),
+        Mode::FeedSettings => "o toggle order | c toggle future dates | Esc close".to_string(),
+        Mode::ExportPath => format!(
+            "Export OPML to: {} (Enter to write, Esc to cancel)",
+            app.input
+        ),
+        Mode::ImportPath => format!(
+            "Import OPML file: {} (Enter to import, Esc to cancel)",
+            app.input
+        ),
+        Mode::SetPriority => format!(
+            "Feed priority (0-255, higher refreshes first): {} (Enter to save, Esc to cancel)",
+            app.input
+        ),
+    }
+}
+
+/// Draws the popup for the current mode, if it has one.
+pub(super) fn draw_popup(frame: &mut Frame, app: &mut App) {
+    match app.mode {
+        Mode::FeedInfo => draw_feed_info(frame, app),
+        Mode::ShowStatus => draw_status_popup(frame, app),
+        Mode::AddPreview => draw_add_preview(frame, app),
+        Mode::FeedSettings => draw_feed_settings(frame, app),
+        Mode::Loading
+        | Mode::LoadFailed
+        | Mode::Normal
+        | Mode::AddUrl
+        | Mode::ConfirmDuplicate
+        | Mode::ConfirmQuit
+        | Mode::SetPriority
+        | Mode::ImportPath
+        | Mode::ExportPath
+        | Mode::Reading => {}
+    }
+}
+
+fn duplicate_prompt(app: &App) -> String {
+    let existing = app.pending_add.as_ref().and_then(|pending| {
+        app.db.feeds.iter().find(|feed| feed.url == pending.existing_url)
+    });
+    let (title, count) = existing.map_or(("Untitled", 0), |feed| {
+        (feed.title.as_deref().unwrap_or("Untitled"), feed.items.len())
+    });
+    format!(
+        "Already subscribed as '{title}' ({count} items). \
+         Refresh it (r), keep both under a different URL (k), or cancel (Esc)?"
+    )
+}
+
+const STATUS_POPUP_WIDTH: u16 = 70;
+
+/// Shows the full status message wrapped to a fixed width, for errors too
+/// long for the status bar.
+fn draw_status_popup(frame: &mut Frame, app: &mut App) {
+    let screen = frame.size();
+    let mut text = app.status.clone();
+    if !app.refresh_errors.is_empty() {
+        text.push_str("\n\nErrors from the last refresh of all feeds:");
+        for error in &app.refresh_errors {
+            text.push_str(&format!("\n- {error}"));
+        }
+    }
+    let width = (STATUS_POPUP_WIDTH + 2).min(screen.width);
+    let text = Paragraph::new(text).wrap(Wrap { trim: false });
+    let line_count = text.line_count(width.saturating_sub(2));
+    let wanted = u16::try_from(line_count.saturating_add(2)).unwrap_or(u16::MAX);
+    let height = wanted
+        .min(screen.height.saturating_sub(2))
+        .max(3)
+        .min(screen.height);
+    let area = Rect::new(
+        screen.x + screen.width.saturating_sub(width) / 2,
+        screen.y + screen.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+
+    let visible = usize::from(height.saturating_sub(2));
+    let max_scroll = u16::try_from(line_count.saturating_sub(visible)).unwrap_or(u16::MAX);
+    app.status_scroll = app.status_scroll.min(max_scroll);
+
+    let popup = text
+        .scroll((app.status_scroll, 0))
+        .block(Block::default().title("Status").borders(Borders::ALL));
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+
+    if max_scroll > 0 {
+        let mut scrollbar = ScrollbarState::new(usize::from(max_scroll) + 1)
+            .position(usize::from(app.status_scroll));
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            area,
+            &mut scrollbar,
+        );
+    }
+}
+
+const ACTIVITY_WEEKS: usize = 12;
+
+fn draw_feed_info(frame: &mut Frame, app: &App) {
+    let Some(feed) = app.selected_feed() else {
+        return;
+    };
+    let published = feed.items.iter().filter_map(|item| {
+        let date = DateTime::parse_from_rfc3339(item.published.as_deref()?).ok()?;
+        Some(date.with_timezone(&Utc))
+    });
+    let label = app.theme.label();
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Title: ", label),
+            Span::raw(feed.title.as_deref().unwrap_or("Untitled")),
+        ]),
+        Line::from(vec![Span::styled("URL: ", label), Span::raw(feed.url.as_str())]),
+        Line::from(vec![
+            Span::styled("Items: ", label),
+            Span::raw(feed.items.len().to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("Last {ACTIVITY_WEEKS} weeks: "), label),
+            Span::raw(sparkline(
+                &weekly_counts(published, Utc::now(), ACTIVITY_WEEKS),
+                app.theme.spark_levels(),
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("Content-Type: ", label),
+            Span::raw(feed.last_content_type.as_deref().unwrap_or("unknown")),
+        ]),
+    ];
+    if let Some(status) = feed.last_http_status {
+        lines.push(Line::from(vec![
+            Span::styled("Last HTTP status: ", label),
+            Span::styled(status.to_string(), app.theme.http_status(status)),
+        ]));
+    }
+    if let Some(until) = &feed.rate_limited_until {
+        lines.push(Line::from(vec![
+            Span::styled("Rate limited until: ", label),
+            Span::raw(until.as_str()),
+        ]));
+    }
+    if let Some(error) = &feed.last_error {
+        lines.push(Line::from(vec![
+            Span::styled("Last error: ", label),
+            Span::styled(error.as_str(), app.theme.error()),
+        ]));
+    }
+    for warning in &feed.warnings {
+        lines.push(Line::from(format!("! {warning}")).style(app.theme.warning()));
+    }
+
+    let area = centered_rect(70, 50, frame.size());
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().title("Feed details").borders(Borders::ALL));
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
 }
 
-fn style_for_focus(is_focused: bool) -> Style {
+fn draw_feed_settings(frame: &mut Frame, app: &App) {
+    let Some(feed) = app.selected_feed() else {
+        return;
+    };
+    let label = app.theme.label();
+    let order = if feed.preserve_order { "feed order" } else { "newest first" };
+    let lines = vec![
+        Line::from(feed.title.as_deref().unwrap_or("Untitled").to_string()),
+        Line::from(""),
+        Line::from(vec![Span::styled("o  Entry order: ", label), Span::raw(order)]),
+        Line::from(vec![
+            Span::styled("c  Future-dated entries: ", label),
+            Span::raw(if feed.clamp_future_dates { "sort as undated" } else { "sort by date" }),
+        ]),
+        Line::from(vec![
+            Span::styled("Ctrl-P  Priority: ", label),
+            Span::raw(feed.priority.to_string()),
+        ]),
+    ];
+
+    let area = centered_rect(50, 30, frame.size());
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().title("Feed settings").borders(Borders::ALL));
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Number of newest entries listed in the add preview.
+const PREVIEW_ITEMS: usize = 3;
+
+fn draw_add_preview(frame: &mut Frame, app: &App) {
+    let Some(preview) = &app.add_preview else {
+        return;
+    };
+    let label = app.theme.label();
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Title: ", label),
+            Span::raw(preview.meta.title.as_deref().unwrap_or("Untitled")),
+        ]),
+        Line::from(vec![Span::styled("URL: ", label), Span::raw(preview.url.as_str())]),
+    ];
+    if let Some(description) = &preview.meta.description {
+        lines.push(Line::from(vec![
+            Span::styled("Description: ", label),
+            Span::raw(description.as_str()),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Items: ", label),
+        Span::raw(preview.items.len().to_string()),
+    ]));
+    let backfill = app.db.backfill;
+    let now = Utc::now();
+    let skipped = preview
+        .items
+        .iter()
+        .filter(|item| backfill.skips(item.published.as_deref(), now))
+        .count();
+    lines.push(Line::from(vec![
+        Span::styled("Backfill: ", label),
+        Span::raw(format!("{backfill} ({skipped} older entries would be skipped)")),
+    ]));
+    let mut newest: Vec<_> = preview.items.iter().collect();
+    newest.sort_by_key(|item| std::cmp::Reverse(parse_published(item.published.as_deref())));
+    if !newest.is_empty() {
+        lines.push(Line::from(Span::styled("Newest:", label)));
+    }
+    for item in newest.into_iter().take(PREVIEW_ITEMS) {
+        let mut spans = vec![Span::raw(format!("- {}", item.title))];
+        if let Some(published) = &item.published {
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(published.as_str(), app.theme.published()));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let area = centered_rect(70, 50, frame.size());
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().title("Subscribe to this feed?").borders(Borders::ALL));
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Marks the focused pane in its title so focus never depends on color alone.
+fn pane_title(name: &str, is_focused: bool) -> String {
     if is_focused {
-        Style::default().fg(Color::Cyan)
+        format!("[{name}]")
     } else {
-        Style::default().fg(Color::DarkGray)
+        name.to_string()
+    }
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::db::{FeedDb, FeedItem};
+    use crate::feed::FeedMeta;
+    use crate::test_util::item;
+    use crate::tui::state::AddPreview;
+
+    fn sample_db() -> FeedDb {
+        let mut db = FeedDb::default();
+        let meta = FeedMeta {
+            title: Some("Example News".to_string()),
+            ..FeedMeta::default()
+        };
+        db.upsert_feed(
+            "https://example.com/feed.xml".to_string(),
+            meta,
+            vec![item("Hello world", "https://example.com/hello")],
+            false,
+            true,
+        );
+        db
+    }
+
+    fn rows(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect())
+            .collect()
+    }
+
+    fn draw(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| draw_ui(frame, app)).unwrap();
+        rows(&terminal)
+    }
+
+    #[test]
+    fn draws_feeds_entries_and_status() {
+        let mut app = App::loaded(sample_db());
+        let rows = draw(&mut app, 100, 20);
+
+        assert!(rows[0].starts_with("rss-cli "));
+        // Feeds pane: "All" is selected and the feed URL is cut at the pane border.
+        assert!(rows[4].starts_with("│   Example News (1)"), "{}", rows[4]);
+        assert!(rows[5].starts_with("│   https://example.com/feed.│"), "{}", rows[5]);
+        // Entries pane starts at 30% of the width.
+        let entries: String = rows[1].chars().skip(30).collect();
+        assert!(entries.starts_with("┌Entries"), "{}", rows[1]);
+        assert!(rows[2].contains("│Hello world"), "{}", rows[2]);
+        assert!(rows[4].contains("│https://example.com/hello"), "{}", rows[4]);
+        assert!(rows[19].starts_with("q quit | a add | r refresh"), "{}", rows[19]);
+    }
+
+    #[test]
+    fn add_preview_shows_details_and_newest_entries() {
+        let dated = |title: &str, day: u32| FeedItem {
+            published: Some(format!("2024-01-{day:02}T00:00:00+00:00")),
+            ..item(title, &format!("https://example.com/{title}"))
+        };
+        let mut app = App::loaded(FeedDb::default());
+        app.add_preview = Some(AddPreview {
+            url: "https://example.com/comments.xml".to_string(),
+            meta: FeedMeta {
+                title: Some("Example comments".to_string()),
+                description: Some("Every comment on every post".to_string()),
+                ..FeedMeta::default()
+            },
+            items: vec![
+                dated("oldest", 1),
+                dated("newest", 4),
+                dated("third", 2),
+                dated("second", 3),
+            ],
+        });
+        app.mode = Mode::AddPreview;
+        let screen = draw(&mut app, 100, 30).join("\n");
+
+        for expected in [
+            "│Title: Example comments",
+            "│URL: https://example.com/comments.xml",
+            "│Description: Every comment on every post",
+            "│Items: 4",
+            "│Backfill: all (0 older entries would be skipped)",
+            "│Newest:",
+        ] {
+            assert!(screen.contains(expected), "missing {expected:?} in\n{screen}");
+        }
+        let position = |title: &str| screen.find(&format!("│- {title} 2024-01-")).unwrap();
+        assert!(position("newest") < position("second"));
+        assert!(position("second") < position("third"));
+        assert!(!screen.contains("- oldest"));
+    }
+
+    #[test]
+    fn status_line_counts_the_position_and_unread_entries() {
+        let mut db = sample_db();
+        let url = db.feeds[0].url.clone();
+        let items = vec![
+            item("Hello world", "https://example.com/hello"),
+            FeedItem { read: true, ..item("Seen", "https://example.com/seen") },
+            item("Later", "https://example.com/later"),
+        ];
+        db.upsert_feed(url, FeedMeta::default(), items, true, false);
+        let mut app = App::loaded(db);
+        app.focus = Focus::Items;
+        app.item_state.select(Some(1));
+        let rows = draw(&mut app, 100, 20);
+
+        assert!(rows[19].starts_with("Item 2 of 3 (2 unread) "), "{}", rows[19]);
+    }
+
+    #[test]
+    fn status_line_shows_read_only_and_messages() {
+        let mut app = App::loaded(sample_db());
+        app.read_only = true;
+        app.status = "Refreshed Example News".to_string();
+        let rows = draw(&mut app, 100, 20);
+
+        assert!(rows[0].starts_with("rss-cli [read-only]"), "{}", rows[0]);
+        assert!(rows[19].starts_with("Refreshed Example News"), "{}", rows[19]);
+    }
+
+    #[test]
+    fn status_popup_scrolls_to_the_last_wrapped_line() {
+        let mut app = App::loaded(sample_db());
+        // Word wrapping needs more rows than a character count would suggest.
+        let words: Vec<String> = (0..60).map(|n| format!("word{n:02}")).collect();
+        app.status = format!("{} END", words.join(" "));
+        app.status_scroll = u16::MAX;
+        app.mode = Mode::ShowStatus;
+        let rows = draw(&mut app, 40, 6);
+
+        assert!(rows[3].contains("END"), "{rows:#?}");
+    }
+
+    #[test]
+    fn status_popup_fits_screens_smaller_than_its_border() {
+        let mut app = App::loaded(sample_db());
+        app.status = "Error: something went wrong".to_string();
+        app.mode = Mode::ShowStatus;
+        for (width, height) in [(1, 1), (10, 2), (2, 10)] {
+            draw(&mut app, width, height);
+        }
     }
 }